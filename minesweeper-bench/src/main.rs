@@ -0,0 +1,98 @@
+use std::time::Instant;
+
+use minesweeper_solver::board::BoardVec;
+use minesweeper_solver::{FirstClickPolicy, Game, GameSetupBuilder};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(Debug, Clone, PartialEq)]
+struct Config {
+  width: u32,
+  height: u32,
+  density: f64,
+  seed: u64,
+  count: u32,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      width: 30,
+      height: 16,
+      density: 0.15,
+      seed: 0,
+      count: 100,
+    }
+  }
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+  let mut config = Config::default();
+  let mut args = args.into_iter();
+
+  while let Some(arg) = args.next() {
+    let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+    match arg.as_str() {
+      "--width" => config.width = parse_number(&value()?)?,
+      "--height" => config.height = parse_number(&value()?)?,
+      "--density" => config.density = parse_number(&value()?)?,
+      "--seed" => config.seed = parse_number(&value()?)?,
+      "--count" => config.count = parse_number(&value()?)?,
+      _ => return Err(format!("Unknown argument {}", arg)),
+    }
+  }
+
+  if config.width == 0 || config.height == 0 {
+    return Err("The board must not be empty".to_string());
+  }
+  if !(0.0..1.0).contains(&config.density) {
+    return Err(format!(
+      "Invalid density {}, expected a value in [0, 1)",
+      config.density
+    ));
+  }
+
+  Ok(config)
+}
+
+fn parse_number<N: std::str::FromStr>(value: &str) -> Result<N, String> {
+  value.parse().map_err(|_| format!("Invalid number {}", value))
+}
+
+fn main() {
+  let config = parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+    eprintln!("{}", err);
+    std::process::exit(1);
+  });
+
+  let start = BoardVec::new((config.width / 2) as i32, (config.height / 2) as i32);
+  let fields = config.width * config.height;
+  let mines = ((fields as f64 * config.density) as u32).min(fields.saturating_sub(9));
+
+  let mut rng = StdRng::seed_from_u64(config.seed);
+  let games: Vec<Game> = (0..config.count)
+    .map(|_| {
+      let board_rng = StdRng::from_rng(&mut rng).unwrap();
+      let mut builder = GameSetupBuilder::with_rng(config.width, config.height, Box::new(board_rng));
+      builder.protect_start(start, FirstClickPolicy::ThreeByThree);
+      builder.add_random_mines(mines);
+      let mut game = Game::from(builder);
+      game.open(start);
+      game
+    })
+    .collect();
+
+  let begin = Instant::now();
+  let solvable = games.into_iter().filter(|game| game.clone().is_solvable()).count();
+  let elapsed = begin.elapsed();
+
+  println!(
+    "{} boards of {}x{} with {} mines: {} solvable",
+    config.count, config.width, config.height, mines, solvable
+  );
+  println!(
+    "total {:.3?}, average {:.3?} per board",
+    elapsed,
+    elapsed / config.count.max(1)
+  );
+}
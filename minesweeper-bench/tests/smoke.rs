@@ -0,0 +1,24 @@
+use std::process::Command;
+
+fn bench(args: &[&str]) -> std::process::Output {
+  Command::new(env!("CARGO_BIN_EXE_minesweeper-bench"))
+    .args(args)
+    .output()
+    .expect("failed to run minesweeper-bench")
+}
+
+#[test]
+fn runs_a_tiny_configuration() {
+  let output = bench(&["--width", "8", "--height", "8", "--count", "3", "--seed", "1"]);
+  assert!(output.status.success());
+  let stdout = String::from_utf8(output.stdout).unwrap();
+  assert!(stdout.starts_with("3 boards of 8x8 with 9 mines: "), "{}", stdout);
+  assert!(stdout.contains("per board"), "{}", stdout);
+}
+
+#[test]
+fn rejects_invalid_arguments() {
+  let output = bench(&["--density", "1.5"]);
+  assert!(!output.status.success());
+  assert!(String::from_utf8_lossy(&output.stderr).contains("Invalid density"));
+}
@@ -0,0 +1,181 @@
+use std::ops::Range;
+
+use minesweeper_solver::board::BoardVec;
+use minesweeper_solver::FirstClickPolicy;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+  pub width: u32,
+  pub height: u32,
+  pub mines: u32,
+  pub seed: Option<u64>,
+  pub start: BoardVec,
+  pub first_click: FirstClickPolicy,
+  pub target_3bv: Option<Range<u32>>,
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      width: 200,
+      height: 40,
+      mines: 1400,
+      seed: None,
+      start: BoardVec::new(100, 20),
+      first_click: FirstClickPolicy::ThreeByThree,
+      target_3bv: None,
+    }
+  }
+}
+
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Config, String> {
+  let mut config = Config::default();
+  let mut start = None;
+  let mut args = args.into_iter();
+
+  while let Some(arg) = args.next() {
+    let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", arg));
+    match arg.as_str() {
+      "--width" => config.width = parse_number(&value()?)?,
+      "--height" => config.height = parse_number(&value()?)?,
+      "--mines" => config.mines = parse_number(&value()?)?,
+      "--seed" => config.seed = Some(parse_number(&value()?)?),
+      "--start" => start = Some(parse_position(&value()?)?),
+      "--first-click" => config.first_click = parse_first_click(&value()?)?,
+      "--target-3bv" => config.target_3bv = Some(parse_range(&value()?)?),
+      _ => return Err(format!("Unknown argument {}", arg)),
+    }
+  }
+
+  if config.width == 0 || config.height == 0 {
+    return Err("The board must not be empty".to_string());
+  }
+  if config.mines as u64 >= config.width as u64 * config.height as u64 {
+    return Err(format!(
+      "Cannot place {} mines on a {}x{} board",
+      config.mines, config.width, config.height
+    ));
+  }
+
+  config.start = start.unwrap_or_else(|| BoardVec::new((config.width / 2) as i32, (config.height / 2) as i32));
+  if config.start.x < 0
+    || config.start.y < 0
+    || config.start.x >= config.width as i32
+    || config.start.y >= config.height as i32
+  {
+    return Err(format!(
+      "Start position {:?} is outside of the {}x{} board",
+      config.start, config.width, config.height
+    ));
+  }
+
+  Ok(config)
+}
+
+fn parse_number<N: std::str::FromStr>(value: &str) -> Result<N, String> {
+  value.parse().map_err(|_| format!("Invalid number {}", value))
+}
+
+fn parse_position(value: &str) -> Result<BoardVec, String> {
+  let (x, y) = value
+    .split_once(',')
+    .ok_or_else(|| format!("Invalid position {}, expected x,y", value))?;
+  Ok(BoardVec::new(parse_number(x.trim())?, parse_number(y.trim())?))
+}
+
+fn parse_range(value: &str) -> Result<Range<u32>, String> {
+  let (start, end) = value
+    .split_once("..")
+    .ok_or_else(|| format!("Invalid range {}, expected min..max", value))?;
+  let range = parse_number(start.trim())?..parse_number(end.trim())?;
+  if range.is_empty() {
+    return Err(format!("Empty range {}, expected min..max with min < max", value));
+  }
+  Ok(range)
+}
+
+fn parse_first_click(value: &str) -> Result<FirstClickPolicy, String> {
+  match value {
+    "cell" => Ok(FirstClickPolicy::CellOnly),
+    "3x3" => Ok(FirstClickPolicy::ThreeByThree),
+    "flood" => Ok(FirstClickPolicy::GuaranteedFlood),
+    _ => Err(format!(
+      "Invalid first click policy {}, expected cell, 3x3 or flood",
+      value
+    )),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(args: &[&str]) -> Result<Config, String> {
+    parse_args(args.iter().map(|arg| arg.to_string()))
+  }
+
+  #[test]
+  fn parses_size_mines_and_seed() {
+    let config = parse(&["--width", "30", "--height", "16", "--mines", "99", "--seed", "42"]).unwrap();
+    assert_eq!(
+      config,
+      Config {
+        width: 30,
+        height: 16,
+        mines: 99,
+        seed: Some(42),
+        start: BoardVec::new(15, 8),
+        ..Config::default()
+      }
+    );
+  }
+
+  #[test]
+  fn start_can_be_overridden() {
+    let config = parse(&["--width", "9", "--height", "9", "--mines", "10", "--start", "0, 8"]).unwrap();
+    assert_eq!(config.start, BoardVec::new(0, 8));
+    assert!(parse(&["--width", "9", "--height", "9", "--mines", "10", "--start", "9,0"]).is_err());
+    assert!(parse(&["--start", "3"]).is_err());
+  }
+
+  #[test]
+  fn rejects_bad_arguments() {
+    assert_eq!(parse(&["--mines"]), Err("Missing value for --mines".to_string()));
+    assert_eq!(parse(&["--mines", "many"]), Err("Invalid number many".to_string()));
+    assert_eq!(parse(&["--size", "9"]), Err("Unknown argument --size".to_string()));
+  }
+
+  #[test]
+  fn parses_the_first_click_policy() {
+    assert_eq!(parse(&[]).unwrap().first_click, FirstClickPolicy::ThreeByThree);
+    assert_eq!(
+      parse(&["--first-click", "flood"]).unwrap().first_click,
+      FirstClickPolicy::GuaranteedFlood
+    );
+    assert_eq!(
+      parse(&["--first-click", "cell"]).unwrap().first_click,
+      FirstClickPolicy::CellOnly
+    );
+    assert!(parse(&["--first-click", "5x5"]).is_err());
+  }
+
+  #[test]
+  fn ranges_must_not_be_empty() {
+    assert_eq!(parse_range("3..9"), Ok(3..9));
+    assert!(parse_range("5..5").is_err());
+    assert!(parse_range("9..3").is_err());
+    assert!(parse_range("9").is_err());
+  }
+
+  #[test]
+  fn rejects_impossible_boards() {
+    assert_eq!(
+      parse(&["--width", "0", "--height", "5"]),
+      Err("The board must not be empty".to_string())
+    );
+    assert_eq!(
+      parse(&["--width", "3", "--height", "3", "--mines", "20"]),
+      Err("Cannot place 20 mines on a 3x3 board".to_string())
+    );
+  }
+}
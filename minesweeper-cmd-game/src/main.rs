@@ -1,55 +1,118 @@
-use minesweeper_solver::board::BoardVec;
-use minesweeper_solver::solver::State;
-use minesweeper_solver::{Game, GameSetupBuilder};
-
-fn make_game() -> Game {
-  let start = BoardVec::new(100, 20);
-  loop {
-    let mut builder = GameSetupBuilder::new(200, 40);
-    builder.protect_all(start.with_neighbours());
-    builder.add_random_mines(1400);
-  
-    let mut game = Game::from(builder);
-    game.open(start);
+use minesweeper_solver::{AutoplayOutcome, Game, GameSetup, GameSetupBuilder};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::config::Config;
+use crate::renderer::TerminalRenderer;
+
+mod config;
+mod renderer;
+
+/// Number of boards `make_game` generates before giving up.
+const MAX_ATTEMPTS: u32 = 1000;
+
+fn make_game(config: &Config) -> Result<Game, String> {
+  let mut rng = match config.seed {
+    Some(seed) => StdRng::seed_from_u64(seed),
+    None => StdRng::from_entropy(),
+  };
+
+  for _ in 0..MAX_ATTEMPTS {
+    let board_rng = StdRng::from_rng(&mut rng).unwrap();
+    let mut builder = GameSetupBuilder::with_rng(config.width, config.height, Box::new(board_rng));
+    builder.protect_start(config.start, config.first_click);
+    if !builder.add_random_mines(config.mines) {
+      return Err(format!(
+        "Cannot place {} mines on a {}x{} board",
+        config.mines, config.width, config.height
+      ));
+    }
+
+    let setup = GameSetup::from(builder);
+    if let Some(target) = &config.target_3bv {
+      if !target.contains(&setup.three_bv()) {
+        continue;
+      }
+    }
+
+    let mut game = Game::from(setup);
+    game.open(config.start);
     if game.clone().is_solvable() {
-      return game;
+      return Ok(game);
     }
   }
+
+  let target = match &config.target_3bv {
+    Some(target) => format!(" with a 3BV in {:?}", target),
+    None => String::new(),
+  };
+  Err(format!(
+    "Found no solvable {}x{} board with {} mines{} in {} attempts",
+    config.width, config.height, config.mines, target, MAX_ATTEMPTS
+  ))
 }
 
 fn main() {
-  //println!("{:?}", game);
-  let mut game = make_game();
-  let mut state = State::from(&game);
+  let config = config::parse_args(std::env::args().skip(1)).unwrap_or_else(|err| {
+    eprintln!("{}", err);
+    std::process::exit(1);
+  });
 
-  loop {
-    let mut suggestions = state.suggestions().collect::<Vec<_>>();
+  let mut game = make_game(&config).unwrap_or_else(|err| {
+    eprintln!("{}", err);
+    std::process::exit(1);
+  });
+  let mut renderer = TerminalRenderer::default();
+  renderer.render(&game).unwrap();
 
-    println!("{:?}", state);
-    println!("{:?}", game);
+  let result = game.autoplay(true);
+  renderer.render(&game).unwrap();
 
-    if game.is_win() {
-      println!("Win!");
-      return;
-    }
+  match result.outcome {
+    AutoplayOutcome::Won => println!("Win after {} moves!", result.moves),
+    AutoplayOutcome::Lost => println!("Lost after {} moves!", result.moves),
+    AutoplayOutcome::Stuck => println!("Not solvable!"),
+  }
+}
 
-    if suggestions.is_empty() {
-      println!("No suggestions.. try to guess...");
-      suggestions = state.deep_suggestion();
-      if suggestions.is_empty() {
-        println!("Not solvable!");
-        return;
-      }
-      println!("Guessed {:?}", suggestions);
-    }
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::parse_args;
 
-    let mut mutator = state.into_mutator();
-    for suggestion in suggestions {
-      for opened in game.open(suggestion).unwrap() {
-        mutator.mark_explored(opened, game.view(opened).unwrap())
-      }
-    }
+  #[test]
+  fn unreachable_3bv_target_gives_up() {
+    let args = [
+      "--width",
+      "5",
+      "--height",
+      "5",
+      "--mines",
+      "3",
+      "--seed",
+      "1",
+      "--target-3bv",
+      "100..200",
+    ];
+    let config = parse_args(args.map(String::from)).unwrap();
+    let err = make_game(&config).unwrap_err();
+    assert!(err.contains("in 1000 attempts"), "{}", err);
+  }
+
+  #[test]
+  fn seeded_game_is_solvable() {
+    let args = ["--width", "8", "--height", "8", "--mines", "10", "--seed", "1"];
+    let config = parse_args(args.map(String::from)).unwrap();
+    let game = make_game(&config).unwrap();
+    assert!(game.is_visible(config.start));
+    assert!(game.is_solvable());
+  }
 
-    state = mutator.finish();
+  #[test]
+  fn too_many_mines_is_an_error() {
+    let args = ["--width", "4", "--height", "4", "--mines", "10", "--first-click", "3x3"];
+    let config = parse_args(args.map(String::from)).unwrap();
+    let err = make_game(&config).unwrap_err();
+    assert_eq!(err, "Cannot place 10 mines on a 4x4 board");
   }
 }
@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+
+use minesweeper_solver::board::{Board, BoardVec};
+use minesweeper_solver::Game;
+
+#[derive(Default)]
+pub struct TerminalRenderer {
+  previous: Option<Board<char>>,
+}
+
+impl TerminalRenderer {
+  pub fn render(&mut self, game: &Game) -> io::Result<()> {
+    let frame = frame(game);
+    let mut out = io::stdout().lock();
+
+    match &self.previous {
+      Some(previous)
+        if (previous.width, previous.height, previous.origin) == (frame.width, frame.height, frame.origin) =>
+      {
+        for pos in changed_cells(previous, &frame) {
+          let screen = pos - frame.origin;
+          write!(out, "\x1b[{};{}H{}", screen.y + 1, screen.x + 1, frame[pos])?;
+        }
+      }
+      _ => {
+        write!(out, "\x1b[2J\x1b[H")?;
+        for (pos, c) in frame.enumerate() {
+          write!(out, "{}", c)?;
+          if pos.x + 1 == frame.origin.x + frame.width as i32 {
+            writeln!(out)?;
+          }
+        }
+      }
+    }
+
+    write!(out, "\x1b[{};1H", frame.height + 1)?;
+    out.flush()?;
+    self.previous = Some(frame);
+    Ok(())
+  }
+}
+
+fn frame(game: &Game) -> Board<char> {
+  game.board().map_with_position(|pos, _| game.render_char(pos))
+}
+
+pub fn changed_cells(previous: &Board<char>, next: &Board<char>) -> Vec<BoardVec> {
+  next
+    .enumerate()
+    .filter(|&(pos, c)| previous.get(pos) != Some(c))
+    .map(|(pos, _)| pos)
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use minesweeper_solver::GameSetup;
+
+  use super::*;
+
+  #[test]
+  fn only_changed_cells_are_redrawn() {
+    let mut game = Game::from(GameSetup::from_reader("*..\n...\n...".as_bytes()).unwrap());
+    let hidden = frame(&game);
+    assert_eq!(changed_cells(&hidden, &hidden), vec![]);
+
+    game.open(BoardVec::new(2, 2));
+    let opened = frame(&game);
+    let changed = changed_cells(&hidden, &opened);
+    assert_eq!(changed.len(), 8);
+    assert!(!changed.contains(&BoardVec::new(0, 0)));
+
+    game.flag(BoardVec::new(0, 0));
+    assert_eq!(changed_cells(&opened, &frame(&game)), vec![BoardVec::new(0, 0)]);
+  }
+}
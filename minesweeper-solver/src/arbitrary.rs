@@ -0,0 +1,100 @@
+use proptest::prelude::*;
+
+use crate::board::{Board, BoardVec};
+use crate::GameSetup;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bounds {
+  pub width: u32,
+  pub height: u32,
+}
+
+impl Default for Bounds {
+  fn default() -> Self {
+    Self { width: 16, height: 16 }
+  }
+}
+
+impl Arbitrary for BoardVec {
+  type Parameters = Bounds;
+  type Strategy = BoxedStrategy<Self>;
+
+  fn arbitrary_with(bounds: Bounds) -> Self::Strategy {
+    (0..bounds.width.max(1) as i32, 0..bounds.height.max(1) as i32)
+      .prop_map(|(x, y)| BoardVec::new(x, y))
+      .boxed()
+  }
+}
+
+impl Arbitrary for GameSetup {
+  type Parameters = Bounds;
+  type Strategy = BoxedStrategy<Self>;
+
+  /// Dimensions range from 1x1 up to `bounds`; shrinking reduces the board size and removes mines.
+  fn arbitrary_with(bounds: Bounds) -> Self::Strategy {
+    (1..=bounds.width.max(1), 1..=bounds.height.max(1))
+      .prop_flat_map(|(width, height)| {
+        proptest::collection::vec(proptest::bool::weighted(0.2), (width * height) as usize).prop_map(
+          move |mines| {
+            let mut board = Board::new(width, height, false);
+            for (field, mine) in board.iter_mut().zip(mines) {
+              *field = mine;
+            }
+            GameSetup::new(&board)
+          },
+        )
+      })
+      .boxed()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use proptest::test_runner::{TestError, TestRunner};
+
+  use super::*;
+  use crate::solver::probability::tests::brute_force_probabilities;
+  use crate::solver::State;
+  use crate::Game;
+
+  const SMALL: Bounds = Bounds { width: 5, height: 4 };
+
+  proptest! {
+    /// Whatever the solver concludes about a small board agrees with trying out all mine placements.
+    #[test]
+    fn solver_agrees_with_brute_force(
+      setup in any_with::<GameSetup>(SMALL),
+      start in any_with::<BoardVec>(SMALL),
+    ) {
+      let start = BoardVec::new(start.x % setup.width() as i32, start.y % setup.height() as i32);
+      let mut game = Game::from(setup);
+      prop_assume!(game.open(start).is_some());
+
+      let state = State::from(&game);
+      let probabilities = state.mine_probabilities();
+      let expected = brute_force_probabilities(&game);
+      for pos in game.board().positions().filter(|&pos| !game.is_visible(pos)) {
+        prop_assert!((probabilities[pos] - expected[pos]).abs() < 1e-9, "{:?} at {:?}", game, pos);
+      }
+      for pos in state.suggestions() {
+        prop_assert_eq!(expected[pos], 0.0);
+      }
+      for pos in state.known_mines() {
+        prop_assert_eq!(expected[pos], 1.0);
+      }
+    }
+  }
+
+  #[test]
+  fn shrinking_reduces_the_board_size() {
+    let mut runner = TestRunner::deterministic();
+    let result = runner.run(&any::<GameSetup>(), |setup| {
+      prop_assert!(setup.width() < 3);
+      Ok(())
+    });
+    match result {
+      Err(TestError::Fail(_, setup)) => assert_eq!((setup.width(), setup.height()), (3, 1)),
+      result => panic!("Expected a failing case, got {:?}", result),
+    }
+  }
+}
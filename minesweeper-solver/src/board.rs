@@ -2,6 +2,9 @@ use core::fmt;
 use std::collections::VecDeque;
 use std::ops::{Add, Index, IndexMut, Neg, Sub};
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 pub static NORTH: BoardVec = BoardVec::new(0, -1);
 pub static NORTH_EAST: BoardVec = BoardVec::new(1, -1);
 pub static EAST: BoardVec = BoardVec::new(1, 0);
@@ -13,11 +16,14 @@ pub static NORTH_WEST: BoardVec = BoardVec::new(-1, -1);
 pub static CENTER: BoardVec = BoardVec::new(0, 0);
 
 pub static DIRECTIONS: [BoardVec; 8] = [NORTH_WEST, NORTH, NORTH_EAST, WEST, EAST, SOUTH_WEST, SOUTH, SOUTH_EAST];
+pub static ORTHOGONAL: [BoardVec; 4] = [NORTH, WEST, EAST, SOUTH];
+pub static DIAGONAL: [BoardVec; 4] = [NORTH_WEST, NORTH_EAST, SOUTH_WEST, SOUTH_EAST];
 pub static CENTER_AND_DIRECTIONS: [BoardVec; 9] = [
   NORTH_WEST, NORTH, NORTH_EAST, WEST, CENTER, EAST, SOUTH_WEST, SOUTH, SOUTH_EAST,
 ];
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoardVec {
   pub x: i32,
   pub y: i32,
@@ -35,6 +41,39 @@ impl BoardVec {
   pub fn neighbours(self) -> impl Iterator<Item = BoardVec> {
     DIRECTIONS.iter().map(move |&dir| dir + self)
   }
+
+  /// Neighbours in the order of `DIRECTIONS`.
+  pub fn neighbour_array(self) -> [BoardVec; 8] {
+    DIRECTIONS.map(|dir| dir + self)
+  }
+
+  /// All positions at most `r` steps away in both directions, excluding `self`, in row-major order.
+  pub fn neighbours_in_radius(self, r: i32) -> impl Iterator<Item = BoardVec> {
+    (-r..=r)
+      .flat_map(move |y| (-r..=r).map(move |x| BoardVec::new(x, y)))
+      .filter(|&dir| dir != CENTER)
+      .map(move |dir| self + dir)
+  }
+
+  pub fn orthogonal_neighbours(self) -> impl Iterator<Item = BoardVec> {
+    ORTHOGONAL.iter().map(move |&dir| dir + self)
+  }
+
+  /// Whether the position lies on a `width`x`height` board with its origin at `(0, 0)`.
+  pub fn in_bounds(self, width: u32, height: u32) -> bool {
+    (0..width as i64).contains(&(self.x as i64)) && (0..height as i64).contains(&(self.y as i64))
+  }
+
+  /// `self + dir`, or `None` if that leaves a `width`x`height` board with its origin at `(0, 0)`.
+  pub fn step(self, dir: BoardVec, width: u32, height: u32) -> Option<BoardVec> {
+    let x = self.x.checked_add(dir.x)?;
+    let y = self.y.checked_add(dir.y)?;
+    Some(BoardVec::new(x, y)).filter(|pos| pos.in_bounds(width, height))
+  }
+
+  pub fn scaled_by(self, sx: i32, sy: i32) -> BoardVec {
+    BoardVec::new(self.x * sx, self.y * sy)
+  }
 }
 
 impl fmt::Debug for BoardVec {
@@ -67,10 +106,110 @@ impl Neg for BoardVec {
   }
 }
 
+/// Maps board positions into another integer coordinate system, e.g. the top-left pixel of each cell on screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BoardTransform {
+  scale: BoardVec,
+  offset: BoardVec,
+}
+
+impl BoardTransform {
+  /// Panics if a component of `scale` is not positive.
+  pub fn new(scale: BoardVec, offset: BoardVec) -> Self {
+    assert!(scale.x > 0 && scale.y > 0, "Scale must be positive, was {:?}", scale);
+    Self { scale, offset }
+  }
+
+  /// Size of one cell in the target system.
+  pub fn scale(&self) -> BoardVec {
+    self.scale
+  }
+
+  /// Target position of the cell at `(0, 0)`.
+  pub fn offset(&self) -> BoardVec {
+    self.offset
+  }
+
+  pub fn apply(&self, pos: BoardVec) -> BoardVec {
+    pos.scaled_by(self.scale.x, self.scale.y) + self.offset
+  }
+
+  /// The position whose cell contains `target`, i.e. the inverse of `apply` rounding towards the top-left.
+  pub fn invert(&self, target: BoardVec) -> BoardVec {
+    let rel = target - self.offset;
+    BoardVec::new(rel.x.div_euclid(self.scale.x), rel.y.div_euclid(self.scale.y))
+  }
+}
+
+/// Number of neighbours a position has within radius `r`. Panics if `r` is negative.
+pub fn neighbourhood_size(r: i32) -> u32 {
+  assert!(r >= 0, "Radius must not be negative, was {}", r);
+  let side = (2 * r + 1) as u32;
+  side * side - 1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutOfBounds {
+  pub pos: BoardVec,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl fmt::Display for OutOfBounds {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Cannot access position {:?} on board with size {}x{}",
+      self.pos, self.width, self.height
+    )
+  }
+}
+
+impl std::error::Error for OutOfBounds {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShapeError {
+  pub width: u32,
+  pub height: u32,
+  pub len: usize,
+}
+
+impl fmt::Display for ShapeError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Cannot create board with size {}x{} from {} fields",
+      self.width, self.height, self.len
+    )
+  }
+}
+
+impl std::error::Error for ShapeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DimensionError {
+  pub width: u32,
+  pub height: u32,
+}
+
+impl fmt::Display for DimensionError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "Cannot create board with size {}x{}: no fields",
+      self.width, self.height
+    )
+  }
+}
+
+impl std::error::Error for DimensionError {}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Board<T> {
   pub width: u32,
   pub height: u32,
+  /// World position of the top-left field. All positions passed to and returned by the board are world positions.
+  pub origin: BoardVec,
   fields: Vec<T>,
 }
 
@@ -79,35 +218,189 @@ impl<T> Board<T> {
   where
     T: Clone,
   {
+    let len = (width as usize)
+      .checked_mul(height as usize)
+      .unwrap_or_else(|| panic!("Cannot create board with size {}x{}: too many fields", width, height));
     Self {
       width,
       height,
-      fields: vec![default; (width * height) as usize],
+      origin: BoardVec::new(0, 0),
+      fields: vec![default; len],
+    }
+  }
+
+  /// Like `new`, but rejects boards without fields.
+  pub fn checked_new(width: u32, height: u32, default: T) -> Result<Self, DimensionError>
+  where
+    T: Clone,
+  {
+    if width == 0 || height == 0 {
+      return Err(DimensionError { width, height });
     }
+    Ok(Self::new(width, height, default))
+  }
+
+  /// Creates a board from fields in row-major order.
+  pub fn from_flat(width: u32, height: u32, fields: Vec<T>) -> Result<Self, ShapeError> {
+    if (width as usize).checked_mul(height as usize) != Some(fields.len()) {
+      return Err(ShapeError {
+        width,
+        height,
+        len: fields.len(),
+      });
+    }
+    Ok(Self {
+      width,
+      height,
+      origin: BoardVec::new(0, 0),
+      fields,
+    })
+  }
+
+  /// The fields in row-major order.
+  pub fn into_flat(self) -> Vec<T> {
+    self.fields
+  }
+
+  pub fn with_origin(mut self, origin: BoardVec) -> Self {
+    self.origin = origin;
+    self
   }
 
   fn pos_to_index(&self, pos: BoardVec) -> Option<usize> {
+    let pos = pos - self.origin;
     match (usize::try_from(pos.x), usize::try_from(pos.y)) {
       (Ok(x), Ok(y)) if x < self.width as usize && y < self.height as usize => Some(x + y * (self.width as usize)),
       _ => None,
     }
   }
 
+  fn mut_index(&self, pos: BoardVec) -> usize {
+    self.pos_to_index(pos).unwrap_or_else(|| {
+      panic!(
+        "Cannot mut-access position {:?} on board with size {}x{}",
+        pos, self.width, self.height
+      )
+    })
+  }
+
+  /// Asserts in debug builds that the fields match the dimensions and that every position maps
+  /// to its own field.
+  pub fn debug_check_layout(&self) {
+    debug_assert_eq!(
+      self.fields.len(),
+      self.width as usize * self.height as usize,
+      "Board of size {}x{} has wrong field count",
+      self.width,
+      self.height
+    );
+    if cfg!(debug_assertions) {
+      let mut seen = vec![false; self.fields.len()];
+      for pos in self.positions() {
+        let index = self.pos_to_index(pos);
+        assert!(
+          matches!(index, Some(i) if i < seen.len() && !std::mem::replace(&mut seen[i], true)),
+          "Position {:?} maps to invalid or duplicate index {:?}",
+          pos,
+          index
+        );
+      }
+    }
+  }
+
   pub fn get(&self, pos: BoardVec) -> Option<&T> {
     self.pos_to_index(pos).and_then(|i| self.fields.get(i))
   }
 
+  pub fn try_get(&self, pos: BoardVec) -> Result<&T, OutOfBounds> {
+    self.get(pos).ok_or(OutOfBounds {
+      pos,
+      width: self.width,
+      height: self.height,
+    })
+  }
+
   pub fn get_mut(&mut self, pos: BoardVec) -> Option<&mut T> {
     self.pos_to_index(pos).and_then(|i| self.fields.get_mut(i))
   }
 
+  pub fn swap(&mut self, a: BoardVec, b: BoardVec) {
+    let (a, b) = (self.mut_index(a), self.mut_index(b));
+    self.fields.swap(a, b);
+  }
+
+  pub fn replace(&mut self, pos: BoardVec, value: T) -> T {
+    std::mem::replace(&mut self[pos], value)
+  }
+
+  /// Number of fields, i.e. `width * height`.
+  pub fn len(&self) -> usize {
+    self.fields.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.fields.is_empty()
+  }
+
+  pub fn contains(&self, pos: BoardVec) -> bool {
+    self.pos_to_index(pos).is_some()
+  }
+
+  /// Positions within `radius` of `pos` that lie on the board.
+  pub fn neighbours_in_bounds(&self, pos: BoardVec, radius: i32) -> impl Iterator<Item = BoardVec> + '_ {
+    pos.neighbours_in_radius(radius).filter(move |&n| self.contains(n))
+  }
+
+  /// Number of neighbours of `pos` on the board: 3 in a corner, 5 at an edge and 8 inside.
+  pub fn neighbour_count(&self, pos: BoardVec) -> u32 {
+    self.neighbour_count_in_radius(pos, 1)
+  }
+
+  pub fn neighbour_count_in_radius(&self, pos: BoardVec, radius: i32) -> u32 {
+    self.neighbours_in_bounds(pos, radius).count() as u32
+  }
+
+  /// The on-board neighbours of `pos` together with their values.
+  pub fn around(&self, pos: BoardVec) -> impl Iterator<Item = (BoardVec, &T)> {
+    self.around_in_radius(pos, 1)
+  }
+
+  pub fn around_in_radius(&self, pos: BoardVec, radius: i32) -> impl Iterator<Item = (BoardVec, &T)> {
+    self.neighbours_in_bounds(pos, radius).map(move |n| (n, &self[n]))
+  }
+
   pub fn get_around(&self, pos: BoardVec) -> impl Iterator<Item = &T> {
-    pos.neighbours().flat_map(|pos| self.get(pos))
+    self.around(pos).map(|(_, value)| value)
+  }
+
+  pub fn count_neighbours(&self, pos: BoardVec, pred: impl Fn(&T) -> bool) -> u32 {
+    self.get_around(pos).filter(|field| pred(field)).count() as u32
   }
 
   pub fn positions(&self) -> BoardPositionIterator {
-    BoardPositionIterator::new(BoardVec::new(0, 0), self.width, self.height)
+    BoardPositionIterator::new(self.origin, self.width, self.height)
+  }
+
+  /// All positions of the board mapped by `transform`, in the order of `positions`.
+  pub fn transform_positions(&self, transform: BoardTransform) -> impl Iterator<Item = BoardVec> {
+    self.positions().map(move |pos| transform.apply(pos))
+  }
+
+  /// Positions of all fields matching `pred`, in row-major order.
+  pub fn filter_positions(&self, pred: impl Fn(BoardVec, &T) -> bool) -> Vec<BoardVec> {
+    self
+      .enumerate()
+      .filter(|&(pos, field)| pred(pos, field))
+      .map(|(pos, _)| pos)
+      .collect()
+  }
+
+  /// Field coordinates relative to the top-left field, in the same order as `positions`.
+  pub fn coords(&self) -> impl Iterator<Item = (u32, u32)> {
+    let width = self.width;
+    (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
   }
+
   pub fn enumerate(&self) -> impl Iterator<Item = (BoardVec, &T)> {
     self.positions().zip(self.fields.iter())
   }
@@ -119,28 +412,237 @@ impl<T> Board<T> {
   pub fn iter(&self) -> impl Iterator<Item = &T> {
     self.fields.iter()
   }
+
+  pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+    self.fields.iter_mut()
+  }
+
+  /// Copies `src` into this board with its top-left field at `top_left`. Fields outside of this board are skipped.
+  pub fn blit(&mut self, top_left: BoardVec, src: &Board<T>)
+  where
+    T: Clone,
+  {
+    for (pos, value) in src.enumerate() {
+      if let Some(field) = self.get_mut(top_left + (pos - src.origin)) {
+        field.clone_from(value);
+      }
+    }
+  }
+
+  /// A copy surrounded by `margin` fields of `fill`. The field at `pos` moves to `pos + (margin, margin)`.
+  pub fn pad(&self, margin: u32, fill: T) -> Board<T>
+  where
+    T: Clone,
+  {
+    let mut padded = Board::new(self.width + 2 * margin, self.height + 2 * margin, fill).with_origin(self.origin);
+    padded.blit(self.origin + BoardVec::new(margin as i32, margin as i32), self);
+    padded
+  }
+
+  /// Every `w`x`h` patch that lies completely inside the board, keyed by its top-left position.
+  /// Panics if `w` or `h` is zero.
+  pub fn windows(&self, w: u32, h: u32) -> impl Iterator<Item = (BoardVec, BoardView<'_, T>)> {
+    assert!(w > 0 && h > 0, "Windows must not be empty, was {}x{}", w, h);
+    let (count_x, count_y) = ((self.width + 1).saturating_sub(w), (self.height + 1).saturating_sub(h));
+    BoardPositionIterator::new(self.origin, count_x, count_y).map(move |pos| {
+      let view = BoardView {
+        board: self,
+        origin: pos,
+        width: w,
+        height: h,
+      };
+      (pos, view)
+    })
+  }
+
+  /// Mirrors the board left to right.
+  pub fn flip_horizontal(&self) -> Board<T>
+  where
+    T: Clone,
+  {
+    let (width, height) = (self.width, self.height);
+    self.remap(width, height, |x, y| (width - 1 - x, y))
+  }
+
+  /// Mirrors the board top to bottom.
+  pub fn flip_vertical(&self) -> Board<T>
+  where
+    T: Clone,
+  {
+    let (width, height) = (self.width, self.height);
+    self.remap(width, height, |x, y| (x, height - 1 - y))
+  }
+
+  pub fn rotate_180(&self) -> Board<T>
+  where
+    T: Clone,
+  {
+    let (width, height) = (self.width, self.height);
+    self.remap(width, height, |x, y| (width - 1 - x, height - 1 - y))
+  }
+
+  /// Mirrors the board along its main diagonal, swapping width and height.
+  pub fn transpose(&self) -> Board<T>
+  where
+    T: Clone,
+  {
+    self.remap(self.height, self.width, |x, y| (y, x))
+  }
+
+  /// Builds a `width`x`height` board whose field at `(x, y)` is this board's field at `source(x, y)`.
+  fn remap(&self, width: u32, height: u32, source: impl Fn(u32, u32) -> (u32, u32)) -> Board<T>
+  where
+    T: Clone,
+  {
+    let fields = (0..height)
+      .flat_map(|y| (0..width).map(move |x| (x, y)))
+      .map(|(x, y)| {
+        let (x, y) = source(x, y);
+        self.fields[x as usize + y as usize * self.width as usize].clone()
+      })
+      .collect();
+    Board {
+      width,
+      height,
+      origin: self.origin,
+      fields,
+    }
+  }
+
+  pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Board<U> {
+    Board {
+      width: self.width,
+      height: self.height,
+      origin: self.origin,
+      fields: self.fields.iter().map(f).collect(),
+    }
+  }
+
+  pub fn map_with_position<U>(&self, mut f: impl FnMut(BoardVec, &T) -> U) -> Board<U> {
+    Board {
+      width: self.width,
+      height: self.height,
+      origin: self.origin,
+      fields: self.enumerate().map(|(pos, value)| f(pos, value)).collect(),
+    }
+  }
+
+  /// Number of fields reachable from `start`, where the flood continues from a field `a` to its neighbour `b`
+  /// if `connected(a, b)`. The blank flood of a click is `|a, _| a.is_blank()`.
+  pub fn component_size(&self, start: BoardVec, connected: impl Fn(&T, &T) -> bool) -> usize {
+    let mut explorer = BoardExplorer::from(self);
+    explorer.enqueue(start);
+    let mut size = 0;
+    while let Some(pos) = explorer.pop() {
+      size += 1;
+      let field = &self[pos];
+      explorer.enqueue_all(self.around(pos).filter(|(_, n)| connected(field, n)).map(|(n, _)| n));
+    }
+    size
+  }
+
+  /// Renders one character per field, with the x coordinate above every tenth column and the y coordinate
+  /// left of each row.
+  pub fn render_with_axes(&self, render_cell: impl Fn(&T) -> char) -> String {
+    let (top, bottom) = (self.origin.y, self.origin.y + self.height as i32 - 1);
+    let label_width = top.to_string().len().max(bottom.to_string().len());
+
+    let mut header: Vec<char> = vec![' '; label_width + 1];
+    for x in 0..self.width as i32 {
+      let world_x = self.origin.x + x;
+      if world_x.rem_euclid(10) == 0 {
+        let column = label_width + 1 + x as usize;
+        header.resize(header.len().max(column), ' ');
+        header.truncate(column);
+        header.extend(world_x.to_string().chars());
+      }
+    }
+    let mut result: String = header.into_iter().collect::<String>().trim_end().to_string();
+    result.push('\n');
+
+    for y in 0..self.height as i32 {
+      result.push_str(&format!("{:>w$} ", self.origin.y + y, w = label_width));
+      for x in 0..self.width as i32 {
+        result.push(render_cell(&self[self.origin + BoardVec::new(x, y)]));
+      }
+      result.push('\n');
+    }
+    result
+  }
+}
+
+#[cfg(feature = "parallel")]
+impl<T: Sync> Board<T> {
+  pub fn par_enumerate(&self) -> impl IndexedParallelIterator<Item = (BoardVec, &T)> {
+    let (width, origin) = (self.width as usize, self.origin);
+    self
+      .fields
+      .par_iter()
+      .enumerate()
+      .map(move |(i, field)| (origin + BoardVec::new((i % width) as i32, (i / width) as i32), field))
+  }
 }
 
 impl<T> Index<BoardVec> for Board<T> {
   type Output = T;
 
   fn index(&self, index: BoardVec) -> &Self::Output {
-    self.get(index).unwrap_or_else(|| {
-      panic!(
-        "Cannot access position {:?} on board with size {}x{}",
-        index, self.width, self.height
-      )
-    })
+    self.try_get(index).unwrap_or_else(|err| panic!("{}", err))
   }
 }
 
 impl<T> IndexMut<BoardVec> for Board<T> {
   fn index_mut(&mut self, index: BoardVec) -> &mut T {
-    let (width, height) = (self.width, self.height);
-    self.get_mut(index).unwrap_or_else(|| {
+    let index = self.mut_index(index);
+    &mut self.fields[index]
+  }
+}
+
+/// A rectangular part of a board, addressed relative to its top-left corner.
+pub struct BoardView<'a, T> {
+  board: &'a Board<T>,
+  origin: BoardVec,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl<'a, T> BoardView<'a, T> {
+  pub fn origin(&self) -> BoardVec {
+    self.origin
+  }
+
+  pub fn get(&self, pos: BoardVec) -> Option<&'a T> {
+    if pos.x < 0 || pos.y < 0 || pos.x >= self.width as i32 || pos.y >= self.height as i32 {
+      return None;
+    }
+    self.board.get(self.origin + pos)
+  }
+
+  pub fn positions(&self) -> BoardPositionIterator {
+    BoardPositionIterator::new(BoardVec::new(0, 0), self.width, self.height)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &'a T> + '_ {
+    self.positions().flat_map(move |pos| self.get(pos))
+  }
+}
+
+impl<'a, T> Clone for BoardView<'a, T> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<'a, T> Copy for BoardView<'a, T> {}
+
+impl<'a, T> Index<BoardVec> for BoardView<'a, T> {
+  type Output = T;
+
+  fn index(&self, index: BoardVec) -> &Self::Output {
+    self.get(index).unwrap_or_else(|| {
       panic!(
-        "Cannot mut-access position {:?} on board with size {}x{}",
-        index, width, height
+        "Cannot access position {:?} on view with size {}x{}",
+        index, self.width, self.height
       )
     })
   }
@@ -184,7 +686,7 @@ impl Iterator for BoardPositionIterator {
   }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct BoardExplorer {
   queue: VecDeque<BoardVec>,
   visited: Board<bool>,
@@ -192,6 +694,18 @@ pub struct BoardExplorer {
 }
 
 impl BoardExplorer {
+  pub fn with_visited(visited: Board<bool>) -> Self {
+    Self {
+      queue: VecDeque::new(),
+      visited,
+      allow_multi: false,
+    }
+  }
+
+  pub fn is_visited(&self, pos: BoardVec) -> bool {
+    self.visited.get(pos).copied().unwrap_or(false)
+  }
+
   pub fn set_allow_multiple_enqueue(&mut self, b: bool) {
     self.allow_multi = b;
   }
@@ -213,6 +727,16 @@ impl BoardExplorer {
     }
   }
 
+  /// Number of positions waiting to be popped.
+  pub fn queued(&self) -> usize {
+    self.queue.len()
+  }
+
+  pub fn reset(&mut self) {
+    self.queue.clear();
+    self.visited.fields.fill(false);
+  }
+
   pub fn pop(&mut self) -> Option<BoardVec> {
     let result = self.queue.pop_front();
     if self.allow_multi {
@@ -227,10 +751,414 @@ impl BoardExplorer {
 
 impl<T> From<&Board<T>> for BoardExplorer {
   fn from(board: &Board<T>) -> Self {
-    Self {
-      queue: VecDeque::new(),
-      visited: Board::new(board.width, board.height, false),
-      allow_multi: false,
+    Self::with_visited(Board::new(board.width, board.height, false).with_origin(board.origin))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn neighbourhood_sizes() {
+    assert_eq!(neighbourhood_size(0), 0);
+    assert_eq!(neighbourhood_size(1), 8);
+    assert_eq!(neighbourhood_size(2), 24);
+    assert_eq!(BoardVec::new(0, 0).neighbours_in_radius(2).count(), 24);
+  }
+
+  #[test]
+  #[should_panic(expected = "Radius must not be negative")]
+  fn negative_neighbourhood_size() {
+    neighbourhood_size(-1);
+  }
+
+  fn flood(explorer: &mut BoardExplorer, walls: &Board<bool>, start: BoardVec) -> Vec<BoardVec> {
+    let mut order = Vec::new();
+    explorer.enqueue(start);
+    while let Some(pos) = explorer.pop() {
+      order.push(pos);
+      if !walls[pos] {
+        explorer.enqueue_all(walls.neighbours_in_bounds(pos, 1));
+      }
+    }
+    order
+  }
+
+  #[test]
+  fn reset_explorer_floods_like_a_fresh_one() {
+    let mut walls = Board::new(5, 4, false);
+    for y in 0..4 {
+      walls[BoardVec::new(2, y)] = true;
+    }
+
+    let mut reused = BoardExplorer::from(&walls);
+    for start in [BoardVec::new(0, 0), BoardVec::new(4, 3)] {
+      reused.reset();
+      let expected = flood(&mut BoardExplorer::from(&walls), &walls, start);
+      assert_eq!(flood(&mut reused, &walls, start), expected);
+      assert_eq!(expected.len(), 12);
+    }
+  }
+
+  #[test]
+  fn swap_and_replace() {
+    use crate::Field;
+
+    let mut board = Board::new(3, 2, Field::Empty(0));
+    board[BoardVec::new(0, 0)] = Field::Mine;
+    board.swap(BoardVec::new(0, 0), BoardVec::new(2, 1));
+    assert_eq!(board[BoardVec::new(0, 0)], Field::Empty(0));
+    assert_eq!(board[BoardVec::new(2, 1)], Field::Mine);
+
+    assert_eq!(board.replace(BoardVec::new(2, 1), Field::Empty(3)), Field::Mine);
+    assert_eq!(board[BoardVec::new(2, 1)], Field::Empty(3));
+  }
+
+  #[test]
+  #[should_panic(expected = "Cannot mut-access position (3, 0) on board with size 3x2")]
+  fn swap_out_of_bounds() {
+    Board::new(3, 2, 0).swap(BoardVec::new(0, 0), BoardVec::new(3, 0));
+  }
+
+  #[test]
+  fn orthogonal_and_diagonal_directions() {
+    let orthogonal: Vec<BoardVec> = CENTER.orthogonal_neighbours().collect();
+    assert_eq!(
+      orthogonal,
+      vec![
+        BoardVec::new(0, -1),
+        BoardVec::new(-1, 0),
+        BoardVec::new(1, 0),
+        BoardVec::new(0, 1)
+      ]
+    );
+    for dir in DIRECTIONS {
+      assert_ne!(ORTHOGONAL.contains(&dir), DIAGONAL.contains(&dir));
+    }
+  }
+
+  #[test]
+  #[cfg(target_pointer_width = "64")]
+  fn huge_boards_are_sized_in_usize() {
+    // 70000 * 70000 overflows u32. Zero-sized fields keep the board from allocating.
+    let board = Board::new(70000, 70000, ());
+    assert_eq!(board.len(), 4_900_000_000);
+    assert!(board.get(BoardVec::new(69999, 69999)).is_some());
+    assert!(board.get(BoardVec::new(70000, 0)).is_none());
+  }
+
+  #[test]
+  #[cfg(feature = "parallel")]
+  fn par_enumerate_matches_enumerate() {
+    let fields = (0..35).collect();
+    let board = Board::from_flat(7, 5, fields)
+      .unwrap()
+      .with_origin(BoardVec::new(-3, 2));
+    let mut parallel: Vec<(BoardVec, &i32)> = board.par_enumerate().collect();
+    parallel.sort_by_key(|&(_, &field)| field);
+    assert_eq!(parallel, board.enumerate().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn iter_mut_sets_every_cell() {
+    let mut board = Board::new(4, 3, 0);
+    for (i, field) in board.iter_mut().enumerate() {
+      *field = i;
+    }
+    assert_eq!(board[BoardVec::new(3, 0)], 3);
+    assert_eq!(board[BoardVec::new(1, 2)], 9);
+    assert!(board
+      .enumerate()
+      .all(|(pos, &field)| field == (pos.x + 4 * pos.y) as usize));
+  }
+
+  #[test]
+  fn neighbour_array_follows_directions() {
+    let pos = BoardVec::new(4, 7);
+    let neighbours = pos.neighbour_array();
+    for (dir, neighbour) in DIRECTIONS.iter().zip(neighbours) {
+      assert_eq!(neighbour - pos, *dir);
+    }
+    assert_eq!(neighbours[0], BoardVec::new(3, 6));
+    assert_eq!(neighbours[7], BoardVec::new(5, 8));
+  }
+
+  #[test]
+  fn try_get_reports_the_position() {
+    let board = Board::new(3, 2, 0);
+    assert_eq!(board.try_get(BoardVec::new(2, 1)), Ok(&0));
+    let err = board.try_get(BoardVec::new(3, 1)).unwrap_err();
+    assert_eq!(
+      err,
+      OutOfBounds {
+        pos: BoardVec::new(3, 1),
+        width: 3,
+        height: 2
+      }
+    );
+    assert_eq!(err.to_string(), "Cannot access position (3, 1) on board with size 3x2");
+  }
+
+  #[test]
+  fn layout_check_accepts_valid_boards() {
+    Board::new(4, 4, 0).debug_check_layout();
+    Board::new(7, 3, 0).debug_check_layout();
+    Board::new(3, 7, 0)
+      .with_origin(BoardVec::new(-2, 5))
+      .debug_check_layout();
+    Board::new(0, 3, 0).debug_check_layout();
+  }
+
+  #[test]
+  #[cfg(debug_assertions)]
+  #[should_panic(expected = "Board of size 7x3 has wrong field count")]
+  fn layout_check_rejects_wrong_field_count() {
+    let mut board = Board::new(7, 3, 0);
+    board.fields.pop();
+    board.debug_check_layout();
+  }
+
+  #[test]
+  fn windows_stay_inside_the_board() {
+    let mut board = Board::new(5, 5, 0);
+    for (pos, field) in board.enumerate_mut() {
+      *field = pos.x + 10 * pos.y;
+    }
+    assert_eq!(board.windows(3, 3).count(), 9);
+    assert_eq!(board.windows(6, 3).count(), 0);
+    assert_eq!(board.windows(5, 5).count(), 1);
+
+    let (pos, view) = board.windows(3, 3).last().unwrap();
+    assert_eq!(pos, BoardVec::new(2, 2));
+    assert_eq!(view[BoardVec::new(0, 0)], 22);
+    assert_eq!(view.get(BoardVec::new(3, 0)), None);
+    assert_eq!(
+      view.iter().copied().collect::<Vec<_>>(),
+      vec![22, 23, 24, 32, 33, 34, 42, 43, 44]
+    );
+  }
+
+  #[test]
+  #[should_panic]
+  fn empty_windows() {
+    Board::new(5, 5, 0).windows(0, 3).count();
+  }
+
+  #[test]
+  fn blit_clips_at_the_edges() {
+    let mut board = Board::new(3, 3, 0);
+    board.blit(BoardVec::new(2, -1), &Board::new(2, 2, 1));
+    assert_eq!(
+      board.iter().copied().collect::<Vec<_>>(),
+      vec![0, 0, 1, 0, 0, 0, 0, 0, 0]
+    );
+
+    let stamp = Board::new(2, 2, 7).with_origin(BoardVec::new(5, 5));
+    board.blit(BoardVec::new(0, 1), &stamp);
+    assert_eq!(
+      board.iter().copied().collect::<Vec<_>>(),
+      vec![0, 0, 1, 7, 7, 0, 7, 7, 0]
+    );
+  }
+
+  #[test]
+  fn pad_centers_the_original() {
+    let mut board = Board::new(2, 2, 0);
+    board[BoardVec::new(0, 0)] = 1;
+    board[BoardVec::new(1, 1)] = 4;
+    let padded = board.pad(1, 9);
+    assert_eq!((padded.width, padded.height), (4, 4));
+    assert_eq!(padded[BoardVec::new(0, 0)], 9);
+    assert_eq!(padded[BoardVec::new(3, 3)], 9);
+    assert_eq!(padded[BoardVec::new(1, 1)], 1);
+    assert_eq!(padded[BoardVec::new(2, 2)], 4);
+  }
+
+  #[test]
+  fn coords_agree_with_positions() {
+    let board = Board::new(4, 3, 0).with_origin(BoardVec::new(-2, 5));
+    assert_eq!(board.coords().count(), 12);
+    for ((x, y), pos) in board.coords().zip(board.positions()) {
+      assert_eq!(BoardVec::new(x as i32 - 2, y as i32 + 5), pos);
+    }
+  }
+
+  #[test]
+  fn flat_round_trip() {
+    let board = Board::from_flat(3, 2, vec![1, 2, 3, 4, 5, 6]).unwrap();
+    assert_eq!(board[BoardVec::new(0, 1)], 4);
+    assert_eq!(board.into_flat(), vec![1, 2, 3, 4, 5, 6]);
+  }
+
+  #[test]
+  fn from_flat_checks_the_shape() {
+    let error = Board::from_flat(3, 3, vec![0; 6]).err();
+    assert_eq!(
+      error,
+      Some(ShapeError {
+        width: 3,
+        height: 3,
+        len: 6
+      })
+    );
+    assert_eq!(
+      error.unwrap().to_string(),
+      "Cannot create board with size 3x3 from 6 fields"
+    );
+  }
+
+  #[test]
+  fn flips_and_transpose() {
+    let board = Board::from_flat(3, 2, vec![1, 2, 3, 4, 5, 6]).unwrap();
+    assert_eq!(board.flip_horizontal().into_flat(), vec![3, 2, 1, 6, 5, 4]);
+    assert_eq!(board.flip_vertical().into_flat(), vec![4, 5, 6, 1, 2, 3]);
+    assert_eq!(board.rotate_180().into_flat(), vec![6, 5, 4, 3, 2, 1]);
+    let transposed = board.transpose();
+    assert_eq!((transposed.width, transposed.height), (2, 3));
+    assert_eq!(transposed.into_flat(), vec![1, 4, 2, 5, 3, 6]);
+  }
+
+  #[test]
+  fn neighbours_in_bounds_skip_outside_positions() {
+    assert_eq!(
+      Board::new(1, 1, ())
+        .neighbours_in_bounds(BoardVec::new(0, 0), 1)
+        .count(),
+      0
+    );
+    assert_eq!(
+      Board::new(2, 1, ())
+        .neighbours_in_bounds(BoardVec::new(0, 0), 1)
+        .count(),
+      1
+    );
+    assert_eq!(
+      Board::new(3, 3, ())
+        .neighbours_in_bounds(BoardVec::new(1, 1), 2)
+        .count(),
+      8
+    );
+    assert!(!Board::new(3, 3, ()).contains(BoardVec::new(3, 0)));
+  }
+
+  #[test]
+  fn around_skips_outside_neighbours() {
+    let mut board = Board::new(4, 3, 0);
+    for (pos, field) in board.enumerate_mut() {
+      *field = pos.x + 10 * pos.y;
+    }
+    let corner: Vec<(BoardVec, &i32)> = board.around(BoardVec::new(0, 0)).collect();
+    assert_eq!(
+      corner,
+      vec![
+        (BoardVec::new(1, 0), &1),
+        (BoardVec::new(0, 1), &10),
+        (BoardVec::new(1, 1), &11)
+      ]
+    );
+    assert_eq!(board.around(BoardVec::new(1, 1)).count(), 8);
+    assert_eq!(board.around_in_radius(BoardVec::new(3, 2), 2).count(), 8);
+  }
+
+  #[test]
+  fn map_with_position_builds_a_parity_board() {
+    let board = Board::new(5, 4, ()).with_origin(BoardVec::new(1, 0));
+    let parity = board.map_with_position(|pos, _| (pos.x + pos.y) % 2 == 0);
+    assert!(!parity[BoardVec::new(1, 0)]);
+    assert!(parity[BoardVec::new(2, 0)]);
+    assert!(parity[BoardVec::new(5, 3)]);
+    assert_eq!(parity.origin, BoardVec::new(1, 0));
+    assert_eq!(parity.iter().filter(|&&even| even).count(), 10);
+  }
+
+  #[test]
+  fn checked_new_rejects_empty_boards() {
+    assert_eq!(
+      Board::checked_new(0, 5, 0).err(),
+      Some(DimensionError { width: 0, height: 5 })
+    );
+    assert_eq!(
+      Board::checked_new(5, 0, 0).err(),
+      Some(DimensionError { width: 5, height: 0 })
+    );
+    assert_eq!(Board::checked_new(2, 3, 1).unwrap().iter().count(), 6);
+  }
+
+  #[test]
+  fn neighbour_count_at_corners_edges_and_inside() {
+    let board = Board::new(4, 3, 0);
+    assert_eq!(board.neighbour_count(BoardVec::new(0, 0)), 3);
+    assert_eq!(board.neighbour_count(BoardVec::new(3, 2)), 3);
+    assert_eq!(board.neighbour_count(BoardVec::new(1, 0)), 5);
+    assert_eq!(board.neighbour_count(BoardVec::new(0, 1)), 5);
+    assert_eq!(board.neighbour_count(BoardVec::new(1, 1)), 8);
+    assert_eq!(board.neighbour_count_in_radius(BoardVec::new(0, 0), 2), 8);
+  }
+
+  #[test]
+  fn in_bounds() {
+    assert!(BoardVec::new(3, 2).in_bounds(4, 3));
+    assert!(!BoardVec::new(4, 2).in_bounds(4, 3));
+    assert!(!BoardVec::new(-1, 0).in_bounds(4, 3));
+    assert!(!BoardVec::new(0, 0).in_bounds(0, 0));
+  }
+
+  #[test]
+  fn render_with_axes_labels_rows_and_every_tenth_column() {
+    let mut board = Board::new(23, 11, false);
+    board[BoardVec::new(10, 10)] = true;
+    let rendered = board.render_with_axes(|&mine| if mine { '*' } else { '.' });
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines.len(), 12);
+    assert_eq!(lines[0], "   0         10        20");
+    assert_eq!(lines[1], format!(" 0 {}", ".".repeat(23)));
+    assert_eq!(lines[11], format!("10 {}*{}", ".".repeat(10), ".".repeat(12)));
+
+    let shifted = Board::new(3, 2, 'x').with_origin(BoardVec::new(8, -1));
+    assert_eq!(shifted.render_with_axes(|&c| c), "     10\n-1 xxx\n 0 xxx\n");
+  }
+
+  #[test]
+  fn len_and_is_empty() {
+    let board = Board::new(4, 3, 0);
+    assert_eq!(board.len(), 12);
+    assert!(!board.is_empty());
+
+    let empty = Board::new(0, 3, 0);
+    assert_eq!(empty.len(), 0);
+    assert!(empty.is_empty());
+  }
+
+  #[test]
+  fn step_stays_on_the_board() {
+    assert_eq!(BoardVec::new(0, 1).step(WEST, 3, 3), None);
+    assert_eq!(BoardVec::new(2, 1).step(EAST, 3, 3), None);
+    assert_eq!(BoardVec::new(1, 0).step(NORTH, 3, 3), None);
+    assert_eq!(BoardVec::new(1, 2).step(SOUTH, 3, 3), None);
+    assert_eq!(BoardVec::new(1, 1).step(SOUTH_EAST, 3, 3), Some(BoardVec::new(2, 2)));
+    assert_eq!(BoardVec::new(i32::MAX, 0).step(EAST, 3, 3), None);
+  }
+
+  #[test]
+  fn transform_round_trip() {
+    let transform = BoardTransform::new(BoardVec::new(16, 12), BoardVec::new(-5, 3));
+    assert_eq!(
+      (transform.scale(), transform.offset()),
+      (BoardVec::new(16, 12), BoardVec::new(-5, 3))
+    );
+    let board = Board::new(4, 3, ());
+    for (pos, target) in board.positions().zip(board.transform_positions(transform)) {
+      assert_eq!(target, BoardVec::new(pos.x * 16 - 5, pos.y * 12 + 3));
+      assert_eq!(transform.invert(target), pos);
+      assert_eq!(transform.invert(target + BoardVec::new(15, 11)), pos);
     }
+    assert_eq!(transform.invert(BoardVec::new(-6, 2)), BoardVec::new(-1, -1));
+  }
+
+  #[test]
+  #[should_panic]
+  fn transform_needs_a_positive_scale() {
+    BoardTransform::new(BoardVec::new(0, 1), BoardVec::new(0, 0));
   }
 }
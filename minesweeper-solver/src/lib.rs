@@ -1,17 +1,32 @@
 use core::fmt;
 use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
 
 use board::{Board, BoardVec};
-use rand::prelude::SliceRandom;
-use rand::RngCore;
-use solver::State;
+use rand::distributions::WeightedIndex;
+use rand::prelude::{Distribution, SliceRandom};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use solver::guess::GuessHeuristic;
+use solver::{Solver, State};
 
 use crate::board::BoardExplorer;
 
 pub mod board;
 pub mod solver;
 
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+#[cfg(feature = "serde")]
+mod serialization;
+
+pub use solver::{ExploredKnowlede, ExploredKnowledeConclusion, FieldKnowledge};
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Field {
   Mine,
   Empty(u32),
@@ -26,10 +41,16 @@ impl Field {
     matches!(self, Field::Empty(0))
   }
 
-  fn notify_mine(field: &mut Field) {
+  pub fn mine_count(self) -> Option<u32> {
+    match self {
+      Field::Mine => None,
+      Field::Empty(mines) => Some(mines),
+    }
+  }
+
+  fn notify_mines(field: &mut Field, count: u32) {
     if let Field::Empty(mines) = field {
-      *mines += 1;
-      //assert!(*mines <= 8);
+      *mines += count;
     }
   }
 }
@@ -44,32 +65,263 @@ impl fmt::Display for Field {
   }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidFieldChar(pub char);
+
+impl fmt::Display for InvalidFieldChar {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Invalid field character {:?}", self.0)
+  }
+}
+
+impl std::error::Error for InvalidFieldChar {}
+
+/// Parses the ASCII notation: `X` or `*` for a mine, a digit for a number and ` ` or `.` for a blank field.
+impl TryFrom<char> for Field {
+  type Error = InvalidFieldChar;
+
+  fn try_from(c: char) -> Result<Self, Self::Error> {
+    match c {
+      '*' | 'X' => Ok(Field::Mine),
+      ' ' | '.' => Ok(Field::Empty(0)),
+      _ => c.to_digit(10).map(Field::Empty).ok_or(InvalidFieldChar(c)),
+    }
+  }
+}
+
+/// A `Field` packed into a single byte: bit 7 marks a mine, bits 0-6 hold the number of adjacent mines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactField(u8);
+
+impl CompactField {
+  const MINE: u8 = 0x80;
+  const COUNT: u8 = 0x7f;
+
+  pub fn raw(self) -> u8 {
+    self.0
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InvalidCompactField;
+
+impl fmt::Display for InvalidCompactField {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Field has no compact encoding")
+  }
+}
+
+impl std::error::Error for InvalidCompactField {}
+
+/// Fails for counts that do not fit into 7 bits.
+impl TryFrom<Field> for CompactField {
+  type Error = InvalidCompactField;
+
+  fn try_from(field: Field) -> Result<Self, Self::Error> {
+    match field {
+      Field::Mine => Ok(CompactField(CompactField::MINE)),
+      Field::Empty(mines) => u8::try_from(mines)
+        .ok()
+        .filter(|&mines| mines <= CompactField::COUNT)
+        .map(CompactField)
+        .ok_or(InvalidCompactField),
+    }
+  }
+}
+
+/// Fails for a mine that also carries a count.
+impl TryFrom<u8> for CompactField {
+  type Error = InvalidCompactField;
+
+  fn try_from(raw: u8) -> Result<Self, Self::Error> {
+    if raw & CompactField::MINE != 0 && raw != CompactField::MINE {
+      Err(InvalidCompactField)
+    } else {
+      Ok(CompactField(raw))
+    }
+  }
+}
+
+impl From<CompactField> for Field {
+  fn from(field: CompactField) -> Self {
+    if field.0 == CompactField::MINE {
+      Field::Mine
+    } else {
+      Field::Empty(u32::from(field.0))
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+  /// Mirroring left to right.
+  Horizontal,
+  /// Mirroring top to bottom.
+  Vertical,
+  Rotational180,
+  /// Mirroring along the main diagonal. Only square boards can have it.
+  Diagonal,
+  /// Mirroring along the anti-diagonal. Only square boards can have it.
+  AntiDiagonal,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SymmetrySet(u8);
+
+impl SymmetrySet {
+  pub fn insert(&mut self, symmetry: Symmetry) {
+    self.0 |= 1 << symmetry as u8;
+  }
+
+  pub fn contains(self, symmetry: Symmetry) -> bool {
+    self.0 & (1 << symmetry as u8) != 0
+  }
+
+  pub fn is_empty(self) -> bool {
+    self.0 == 0
+  }
+
+  pub fn iter(self) -> impl Iterator<Item = Symmetry> {
+    use Symmetry::*;
+    [Horizontal, Vertical, Rotational180, Diagonal, AntiDiagonal]
+      .into_iter()
+      .filter(move |&symmetry| self.contains(symmetry))
+  }
+}
+
 pub type GameBoard = Board<Field>;
+pub type CompactBoard = Board<CompactField>;
 pub type ViewBoard = Board<bool>;
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct GameSetup {
   board: GameBoard,
+  /// Number of cells with mines.
   mines: u32,
+  /// Number of mines, counting every mine of a cell that holds several.
+  total_mines: u32,
+  /// The most mines a cell may hold by the rules of the variant, whether or not any cell does.
+  max_mines_per_cell: u32,
+  radius: i32,
 }
 
 impl GameSetup {
   pub fn new(bombs: &Board<bool>) -> Self {
-    let mut board = GameBoard::new(bombs.width, bombs.height, Field::Empty(0));
-    let mut mines = 0;
-    for (pos, &is_mine) in bombs.enumerate() {
-      if is_mine {
+    Self::with_radius(bombs, 1)
+  }
+
+  /// A setup where each number counts the mines within `radius`, e.g. the surrounding 5x5 cells for radius 2.
+  /// Panics if `radius` is smaller than 1.
+  pub fn with_radius(bombs: &Board<bool>, radius: i32) -> Self {
+    assert_valid_radius(radius);
+    Self::from_mine_counts_in_radius(&bombs.map(|&is_mine| is_mine as u32), radius, 1)
+  }
+
+  /// Creates a setup where a cell may hold up to `max_mines_per_cell` mines, which all count towards the
+  /// neighbours' numbers. Panics if `max_mines_per_cell` is zero or a cell holds more mines.
+  pub fn from_mine_counts(counts: &Board<u32>, max_mines_per_cell: u32) -> Self {
+    Self::from_mine_counts_in_radius(counts, 1, max_mines_per_cell)
+  }
+
+  fn from_mine_counts_in_radius(counts: &Board<u32>, radius: i32, max_mines_per_cell: u32) -> Self {
+    assert_valid_capacity(max_mines_per_cell);
+    let mut board = GameBoard::new(counts.width, counts.height, Field::Empty(0)).with_origin(counts.origin);
+    let (mut mines, mut total_mines) = (0, 0);
+    for (pos, &count) in counts.enumerate() {
+      if count > 0 {
+        assert!(
+          count <= max_mines_per_cell,
+          "{:?} holds {} mines, but a cell may hold at most {}",
+          pos,
+          count,
+          max_mines_per_cell
+        );
         mines += 1;
+        total_mines += count;
         board[pos] = Field::Mine;
-        for neighbour_pos in pos.neighbours() {
+        for neighbour_pos in pos.neighbours_in_radius(radius) {
           if let Some(neighbour) = board.get_mut(neighbour_pos) {
-            Field::notify_mine(neighbour);
+            Field::notify_mines(neighbour, count);
           }
         }
       }
     }
 
-    GameSetup { board, mines }
+    GameSetup {
+      board,
+      mines,
+      total_mines,
+      max_mines_per_cell,
+      radius,
+    }
+  }
+
+  /// A builder with the mines of this setup and nothing protected, for editing the setup.
+  /// Cells that held several mines come back with a single one.
+  pub fn to_builder(&self) -> GameSetupBuilder {
+    let mut builder = GameSetupBuilder::new(self.width(), self.height());
+    builder.mines = self.board.map(|field| field.is_mine() as u32);
+    builder.protected = self.board.map(|_| false);
+    builder.radius = self.radius;
+    builder.max_mines_per_cell = self.max_mines_per_cell;
+    builder
+  }
+
+  /// Number of mines, counting every mine of a cell that holds several.
+  pub fn total_mines(&self) -> u32 {
+    self.total_mines
+  }
+
+  /// The most mines a cell may hold by the rules of the variant, which is what the solver assumes.
+  pub fn max_mines_per_cell(&self) -> u32 {
+    self.max_mines_per_cell
+  }
+
+  /// The distance up to which mines count towards a number.
+  pub fn radius(&self) -> i32 {
+    self.radius
+  }
+
+  /// Reads a board where `*` or `X` marks a mine and digits, spaces or `.` mark safe cells, e.g. the `Debug`
+  /// output of a setup. The numbers are recomputed from the mines. All lines must have the same length,
+  /// except for trailing whitespace beyond the shortest line. Empty lines at the end are ignored.
+  pub fn from_reader(reader: impl BufRead) -> io::Result<GameSetup> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    // Each row with the number of its fields before any trailing spaces.
+    let mut rows: Vec<(Vec<bool>, usize)> = Vec::new();
+    for line in reader.lines() {
+      let line = line?;
+      let line = line.trim_end_matches(|c: char| c.is_whitespace() && c != ' ');
+      let row = parse_ascii_line(line)
+        .map_err(|InvalidFieldChar(c)| invalid(format!("Invalid character {:?} in line {}", c, rows.len() + 1)))?;
+      rows.push((row, line.trim_end_matches(' ').chars().count()));
+    }
+
+    while rows.last().is_some_and(|(row, _)| row.is_empty()) {
+      rows.pop();
+    }
+    if rows.is_empty() {
+      return Err(invalid("Empty board".to_string()));
+    }
+
+    // Spaces are blank cells, so the board is as wide as the shortest line and longer lines may only add spaces.
+    let width = rows.iter().map(|(row, _)| row.len()).min().unwrap_or(0);
+    if let Some((y, &(_, len))) = rows.iter().enumerate().find(|(_, &(_, len))| len > width) {
+      return Err(invalid(format!(
+        "Line {} has {} fields, but the shortest line has {}",
+        y + 1,
+        len,
+        width
+      )));
+    }
+
+    let fields = rows
+      .into_iter()
+      .flat_map(|(row, _)| row.into_iter().take(width))
+      .collect::<Vec<_>>();
+    let height = (fields.len() / width) as u32;
+    let mines = Board::from_flat(width as u32, height, fields).expect("all rows have the same width");
+    Ok(GameSetup::new(&mines))
   }
 
   pub fn width(&self) -> u32 {
@@ -79,51 +331,295 @@ impl GameSetup {
   pub fn height(&self) -> u32 {
     self.board.height
   }
+
+  /// The board with one byte per field. Fails only for radii large enough to exceed 127 neighbours.
+  pub fn compact_board(&self) -> Result<CompactBoard, InvalidCompactField> {
+    let fields = self
+      .board
+      .iter()
+      .map(|&field| CompactField::try_from(field))
+      .collect::<Result<Vec<_>, _>>()?;
+    let board = Board::from_flat(self.board.width, self.board.height, fields).expect("same shape as the board");
+    Ok(board.with_origin(self.board.origin))
+  }
+
+  /// Size of the largest area a single click can open.
+  pub fn largest_open_region(&self) -> usize {
+    let mut largest = if self.board.iter().any(|field| !field.is_mine()) {
+      1
+    } else {
+      0
+    };
+    // The explorer only walks blank cells, so each is visited once over all regions. A number can border
+    // several regions, so it remembers the start of the last region it was counted for instead.
+    let mut explorer = BoardExplorer::from(&self.board);
+    let mut counted_for = self.board.map(|_| None);
+
+    for (start, field) in self.board.enumerate() {
+      if !field.is_blank() || !explorer.enqueue(start) {
+        continue;
+      }
+
+      let mut size = 0;
+      while let Some(pos) = explorer.pop() {
+        size += 1;
+        for neighbour_pos in pos.neighbours_in_radius(self.radius) {
+          match self.board.get(neighbour_pos) {
+            Some(neighbour) if neighbour.is_blank() => {
+              explorer.enqueue(neighbour_pos);
+            }
+            Some(_) if counted_for[neighbour_pos] != Some(start) => {
+              counted_for[neighbour_pos] = Some(start);
+              size += 1;
+            }
+            _ => {}
+          }
+        }
+      }
+      largest = largest.max(size);
+    }
+
+    largest
+  }
+
+  /// The reflections and rotations that leave the mine layout unchanged.
+  pub fn symmetries(&self) -> SymmetrySet {
+    let mines = self.board.map(|field| field.is_mine());
+    let mut symmetries = SymmetrySet::default();
+    let mut check = |symmetry, transformed: Board<bool>| {
+      if transformed == mines {
+        symmetries.insert(symmetry);
+      }
+    };
+
+    check(Symmetry::Horizontal, mines.flip_horizontal());
+    check(Symmetry::Vertical, mines.flip_vertical());
+    check(Symmetry::Rotational180, mines.rotate_180());
+    if mines.width == mines.height {
+      check(Symmetry::Diagonal, mines.transpose());
+      check(Symmetry::AntiDiagonal, mines.transpose().rotate_180());
+    }
+    symmetries
+  }
+
+  /// Bechtel's Board Benchmark Value: the minimum number of clicks needed to clear the board,
+  /// i.e. one per blank region plus one per number that no blank region opens.
+  pub fn three_bv(&self) -> u32 {
+    let mut explorer = BoardExplorer::from(&self.board);
+    let mut clicks = 0;
+
+    for (start, field) in self.board.enumerate() {
+      if !field.is_blank() || explorer.is_visited(start) {
+        continue;
+      }
+
+      clicks += 1;
+      explorer.enqueue(start);
+      while let Some(pos) = explorer.pop() {
+        if self.board[pos].is_blank() {
+          explorer.enqueue_all(pos.neighbours_in_radius(self.radius));
+        }
+      }
+    }
+
+    let unopened_numbers = self
+      .board
+      .enumerate()
+      .filter(|&(pos, field)| !field.is_mine() && !explorer.is_visited(pos))
+      .count();
+    clicks + unopened_numbers as u32
+  }
+
+  /// Number of mines around `pos`, or 0 outside of the board. Mines don't store their number, so their
+  /// neighbouring mine cells are counted instead.
+  pub fn adjacent_mines(&self, pos: BoardVec) -> u32 {
+    match self.board.get(pos) {
+      Some(Field::Empty(mines)) => *mines,
+      Some(Field::Mine) => self
+        .board
+        .around_in_radius(pos, self.radius)
+        .filter(|(_, field)| field.is_mine())
+        .count() as u32,
+      None => 0,
+    }
+  }
+}
+
+fn assert_valid_radius(radius: i32) {
+  assert!(radius >= 1, "Radius must be at least 1, was {}", radius);
+}
+
+fn assert_valid_capacity(max_mines_per_cell: u32) {
+  assert!(max_mines_per_cell >= 1, "A cell must be able to hold a mine");
+}
+
+fn parse_ascii_line(line: &str) -> Result<Vec<bool>, InvalidFieldChar> {
+  line.chars().map(|c| Field::try_from(c).map(Field::is_mine)).collect()
 }
 
 impl<B: Borrow<GameSetupBuilder>> From<B> for GameSetup {
   fn from(builder: B) -> Self {
     let builder: &GameSetupBuilder = builder.borrow();
-    Self::new(&builder.mines)
+    Self::from_mine_counts_in_radius(&builder.mines, builder.radius, builder.max_mines_per_cell)
   }
 }
 
 impl fmt::Debug for GameSetup {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    for y in 0..self.height() {
-      for x in 0..self.width() {
-        let pos = BoardVec::new(x as i32, y as i32);
-        write!(f, "{}", self.board[pos])?;
+    if f.alternate() {
+      return write_annotated(f, &self.board, self.mines, |pos| self.board[pos]);
+    }
+    for (pos, field) in self.board.enumerate() {
+      write!(f, "{}", field)?;
+      if pos.x + 1 == self.board.origin.x + self.width() as i32 {
+        writeln!(f)?;
       }
-      writeln!(f)?;
     }
 
     Ok(())
   }
 }
 
+/// Writes the size and mine count followed by the grid with column and row numbers (modulo 10 for columns).
+fn write_annotated<T, D: fmt::Display>(
+  f: &mut fmt::Formatter<'_>,
+  board: &Board<T>,
+  mines: u32,
+  cell: impl Fn(BoardVec) -> D,
+) -> fmt::Result {
+  let (top, bottom) = (board.origin.y, board.origin.y + board.height as i32 - 1);
+  let label_width = top.to_string().len().max(bottom.to_string().len());
+  writeln!(f, "{}x{}, {} mines", board.width, board.height, mines)?;
+  write!(f, "{:w$} ", "", w = label_width)?;
+  for x in 0..board.width as i32 {
+    write!(f, "{}", (board.origin.x + x).rem_euclid(10))?;
+  }
+  writeln!(f)?;
+  for y in 0..board.height as i32 {
+    write!(f, "{:>w$} ", board.origin.y + y, w = label_width)?;
+    for x in 0..board.width as i32 {
+      write!(f, "{}", cell(board.origin + BoardVec::new(x, y)))?;
+    }
+    writeln!(f)?;
+  }
+
+  Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirstClickPolicy {
+  /// Only the clicked cell is free of mines.
+  CellOnly,
+  /// The clicked cell and its neighbours are free of mines, so the first click opens a blank cell.
+  ThreeByThree,
+  /// Everything within two cells is free of mines, so the neighbours of the first click are blank as well.
+  GuaranteedFlood,
+}
+
+impl FirstClickPolicy {
+  pub fn safe_cells(self, start: BoardVec) -> impl Iterator<Item = BoardVec> {
+    let radius = match self {
+      FirstClickPolicy::CellOnly => 0,
+      FirstClickPolicy::ThreeByThree => 1,
+      FirstClickPolicy::GuaranteedFlood => 2,
+    };
+    (-radius..=radius).flat_map(move |y| (-radius..=radius).map(move |x| start + BoardVec::new(x, y)))
+  }
+}
+
+/// Number of game replays `GameSetupBuilder::generate_no_guess` may spend before giving up.
+const NO_GUESS_CHECKS: usize = 5000;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continues the 64 bit FNV-1a hash `hash` with `bytes`.
+fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+  bytes.iter().fold(hash, |hash, &byte| {
+    (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+  })
+}
+
 pub struct GameSetupBuilder {
-  mines: Board<bool>,
+  mines: Board<u32>,
   protected: Board<bool>,
+  pre_opened: Vec<BoardVec>,
+  radius: i32,
+  max_mines_per_cell: u32,
   rng: Box<dyn RngCore>,
 }
 
 impl GameSetupBuilder {
   pub fn new(width: u32, height: u32) -> Self {
+    Self::with_rng(width, height, Box::new(rand::thread_rng()))
+  }
+
+  /// Panics if `width` or `height` is zero.
+  pub fn with_rng(width: u32, height: u32, rng: Box<dyn RngCore>) -> Self {
+    let mines = Board::checked_new(width, height, 0).unwrap_or_else(|err| panic!("{}", err));
     Self {
-      mines: Board::new(width, height, false),
+      mines,
       protected: Board::new(width, height, false),
-      rng: Box::new(rand::thread_rng()),
+      pre_opened: Vec::new(),
+      radius: 1,
+      max_mines_per_cell: 1,
+      rng,
     }
   }
 
+  /// Replaces the random number generator used for placing mines from now on.
+  pub fn set_rng(&mut self, rng: Box<dyn RngCore>) {
+    self.rng = rng;
+  }
+
+  /// Sets the distance up to which mines count towards a number, see `GameSetup::with_radius`.
+  /// Panics if `radius` is smaller than 1.
+  pub fn set_radius(&mut self, radius: i32) {
+    assert_valid_radius(radius);
+    self.radius = radius;
+  }
+
+  /// Lets a cell hold up to `max_mines_per_cell` mines, see `GameSetup::from_mine_counts`. Defaults to 1.
+  /// Panics if `max_mines_per_cell` is zero or a cell already holds more mines.
+  pub fn set_max_mines_per_cell(&mut self, max_mines_per_cell: u32) {
+    assert_valid_capacity(max_mines_per_cell);
+    assert!(self.mines.iter().all(|&count| count <= max_mines_per_cell));
+    self.max_mines_per_cell = max_mines_per_cell;
+  }
+
   pub fn has_mine(&self, pos: BoardVec) -> bool {
+    self.mines[pos] > 0
+  }
+
+  pub fn mine_count(&self, pos: BoardVec) -> u32 {
     self.mines[pos]
   }
 
   pub fn set_mine(&mut self, pos: BoardVec) {
     assert!(!self.is_protected(pos));
-    self.mines[pos] = true;
+    self.mines[pos] = self.mines[pos].max(1);
+  }
+
+  /// Panics if the cell is protected or already holds `max_mines_per_cell` mines.
+  pub fn add_mine(&mut self, pos: BoardVec) {
+    assert!(!self.is_protected(pos));
+    assert!(self.mines[pos] < self.max_mines_per_cell);
+    self.mines[pos] += 1;
+  }
+
+  pub fn clear_mine(&mut self, pos: BoardVec) {
+    self.mines[pos] = 0;
+  }
+
+  /// Returns false if the cell is protected and therefore cannot get a mine.
+  pub fn toggle_mine(&mut self, pos: BoardVec) -> bool {
+    if self.has_mine(pos) {
+      self.clear_mine(pos);
+    } else if self.is_protected(pos) {
+      return false;
+    } else {
+      self.set_mine(pos);
+    }
+    true
   }
 
   pub fn is_protected(&self, pos: BoardVec) -> bool {
@@ -132,7 +628,7 @@ impl GameSetupBuilder {
 
   pub fn protect(&mut self, pos: BoardVec) {
     if self.mines.get(pos).is_some() {
-      self.mines[pos] = false;
+      self.mines[pos] = 0;
       self.protected[pos] = true;
     }
   }
@@ -143,6 +639,18 @@ impl GameSetupBuilder {
     }
   }
 
+  /// Lets games built from this builder start with `pos` opened, including the flood if it is blank.
+  /// Unlike `protect`, mines may still be placed there, in which case the cell stays hidden.
+  pub fn pre_open(&mut self, pos: BoardVec) {
+    if self.mines.get(pos).is_some() {
+      self.pre_opened.push(pos);
+    }
+  }
+
+  pub fn protect_start(&mut self, start: BoardVec, policy: FirstClickPolicy) {
+    self.protect_all(policy.safe_cells(start));
+  }
+
   pub fn add_random_mines(&mut self, mut mines: u32) -> bool {
     let mut possible_positions: Vec<_> = self.mines.positions().collect();
     possible_positions.shuffle(&mut self.rng);
@@ -162,13 +670,217 @@ impl GameSetupBuilder {
 
     false
   }
+
+  pub fn add_weighted_mines(&mut self, mut mines: u32, weights: &Board<f64>) -> bool {
+    let mut candidates: Vec<(BoardVec, f64)> = self
+      .mines
+      .positions()
+      .filter(|&pos| !self.is_protected(pos) && !self.has_mine(pos))
+      .map(|pos| (pos, weights.get(pos).copied().unwrap_or(0.0)))
+      .filter(|&(_, weight)| weight > 0.0)
+      .collect();
+
+    while mines > 0 {
+      let distribution = match WeightedIndex::new(candidates.iter().map(|&(_, weight)| weight)) {
+        Ok(distribution) => distribution,
+        Err(_) => return false,
+      };
+
+      let (pos, _) = candidates.swap_remove(distribution.sample(&mut self.rng));
+      self.set_mine(pos);
+      mines -= 1;
+    }
+
+    true
+  }
+
+  /// Adds `mines` mines one by one, keeping only placements after which the game opened at `start`
+  /// can still be won by plain deduction. When the candidates run out, the latest placement is undone
+  /// and the next candidate is tried instead. Gives up after `NO_GUESS_CHECKS` replays of the game,
+  /// so this is meant for small boards.
+  pub fn generate_no_guess(
+    &self,
+    mines: u32,
+    start: BoardVec,
+    policy: FirstClickPolicy,
+    rng: &mut dyn RngCore,
+  ) -> Option<Game> {
+    let mut counts = self.mines.clone();
+    let mut safe = counts.map(|_| false);
+    for pos in policy.safe_cells(start) {
+      if let Some(count) = counts.get_mut(pos) {
+        *count = 0;
+        safe[pos] = true;
+      }
+    }
+
+    let mut candidates: Vec<BoardVec> = counts
+      .positions()
+      .filter(|&pos| !safe[pos] && !self.is_protected(pos) && counts[pos] == 0)
+      .collect();
+    candidates.shuffle(rng);
+
+    // Indices into `candidates` of the accepted placements, in order.
+    let mut placed: Vec<usize> = Vec::new();
+    let mut next = 0;
+    let mut checks = 0;
+    while placed.len() < mines as usize {
+      if candidates.len() - next < mines as usize - placed.len() {
+        let last = placed.pop()?;
+        counts[candidates[last]] = 0;
+        next = last + 1;
+        continue;
+      }
+      if checks == NO_GUESS_CHECKS {
+        return None;
+      }
+      checks += 1;
+
+      let pos = candidates[next];
+      counts[pos] = 1;
+      if self.is_no_guess(&counts, start) {
+        placed.push(next);
+      } else {
+        counts[pos] = 0;
+      }
+      next += 1;
+    }
+
+    let mut game = Game::from(GameSetup::from_mine_counts_in_radius(
+      &counts,
+      self.radius,
+      self.max_mines_per_cell,
+    ));
+    game.open(start);
+    Some(game)
+  }
+
+  fn is_no_guess(&self, counts: &Board<u32>, start: BoardVec) -> bool {
+    let mut game = Game::from(GameSetup::from_mine_counts_in_radius(
+      counts,
+      self.radius,
+      self.max_mines_per_cell,
+    ));
+    game.open(start).is_some() && game.autoplay(false).outcome == AutoplayOutcome::Won
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AutoplayOutcome {
+  Won,
+  Lost,
+  Stuck,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AutoplayResult {
+  pub outcome: AutoplayOutcome,
+  pub moves: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum OpenOutcome {
+  Opened {
+    opened: Vec<BoardVec>,
+    flagged: Vec<BoardVec>,
+    /// True if this open revealed the last safe cell.
+    won: bool,
+  },
+  HitMine(BoardVec),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LimitedOpen {
+  pub opened: Vec<BoardVec>,
+  /// True if the flood stopped at the limit and `resume_open` has more cells to open.
+  pub pending: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RevealedMines {
+  pub flagged: Vec<BoardVec>,
+  pub missed: Vec<BoardVec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hint {
+  OpenSafe(BoardVec),
+  FlagMine(BoardVec),
+  Guess { pos: BoardVec, probability: f64 },
+}
+
+/// A move a player can make, e.g. received from a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameAction {
+  Open(BoardVec),
+  Flag(BoardVec),
+  Chord(BoardVec),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellSummary {
+  pub number: u32,
+  pub hidden: u32,
+  pub flagged: u32,
+  pub opened: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameEvent {
+  CellOpened(BoardVec),
+  MineHit(BoardVec),
+  Flagged(BoardVec),
+  Won,
+  Lost,
+}
+
+/// Holds the observer of a game. It is not part of the game state: clones start without an observer,
+/// and it is ignored by comparison and hashing.
+#[derive(Default)]
+struct Observer(Option<Box<dyn FnMut(GameEvent) + Send>>);
+
+impl Clone for Observer {
+  fn clone(&self) -> Self {
+    Self(None)
+  }
+}
+
+impl PartialEq for Observer {
+  fn eq(&self, _other: &Self) -> bool {
+    true
+  }
+}
+
+impl Eq for Observer {}
+
+impl Hash for Observer {
+  fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WinCondition {
+  /// Every field without a mine is open.
+  #[default]
+  OpenAllSafe,
+  /// Every mine is flagged and no other field is.
+  FlagAllMines,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Game {
   setup: GameSetup,
   view: ViewBoard,
+  flagged: ViewBoard,
   hidden_fields: u32,
+  win_condition: WinCondition,
+  flagged_mines: u32,
+  flagged_safe: u32,
+  lost: bool,
+  explorer: Option<BoardExplorer>,
+  /// Cells an interrupted `open_limited` still has to visit.
+  pending: Vec<BoardVec>,
+  observer: Observer,
 }
 
 impl Game {
@@ -177,7 +889,32 @@ impl Game {
   }
 
   pub fn is_win(&self) -> bool {
-    self.hidden_fields == self.setup.mines
+    match self.win_condition {
+      WinCondition::OpenAllSafe => self.hidden_fields == self.setup.mines,
+      WinCondition::FlagAllMines => self.flagged_mines == self.setup.mines && self.flagged_safe == 0,
+    }
+  }
+
+  pub fn win_condition(&self) -> WinCondition {
+    self.win_condition
+  }
+
+  pub fn set_win_condition(&mut self, win_condition: WinCondition) {
+    self.win_condition = win_condition;
+  }
+
+  pub fn is_lost(&self) -> bool {
+    self.lost
+  }
+
+  /// Fraction of safe cells that have been opened.
+  pub fn progress(&self) -> f64 {
+    let safe_fields = self.board().len() as u32 - self.setup.mines;
+    if safe_fields == 0 {
+      return 1.0;
+    }
+    let opened = self.board().len() as u32 - self.hidden_fields;
+    opened as f64 / safe_fields as f64
   }
 
   pub fn board(&self) -> &GameBoard {
@@ -204,60 +941,477 @@ impl Game {
     }
   }
 
-  pub fn open(&mut self, pos: BoardVec) -> Option<Vec<BoardVec>> {
-    //assert!(!self.is_visible(pos));
-    if self.board()[pos].is_mine() {
-      return None;
+  /// Whether `action` is a meaningful move in the current state: the game is still running, the position is
+  /// on the board, opened and flagged cells are hidden and unflagged, and chorded cells are open numbers.
+  pub fn is_valid_move(&self, action: &GameAction) -> bool {
+    if self.is_win() || self.is_lost() {
+      return false;
     }
-
-    let mut explorer = BoardExplorer::from(self.board());
-    explorer.enqueue(pos);
-
-    let mut opened = Vec::new();
-    while let Some(pos) = explorer.pop() {
-      if !self.is_visible(pos) {
-        self.view[pos] = true;
-        self.hidden_fields -= 1;
-        debug_assert!(self.hidden_fields >= self.setup.mines);
-        opened.push(pos);
-        if self.board()[pos].is_blank() {
-          explorer.enqueue_all(pos.neighbours());
-        }
+    match *action {
+      GameAction::Open(pos) | GameAction::Flag(pos) => self.view.get(pos) == Some(&false) && !self.is_flagged(pos),
+      GameAction::Chord(pos) => {
+        self.view.get(pos) == Some(&true) && matches!(self.view(pos), Some(Field::Empty(mines)) if mines > 0)
       }
     }
+  }
 
-    Some(opened)
+  /// Shorthand for `open(BoardVec::new(x, y))`, as are the other `_at` methods.
+  pub fn open_at(&mut self, x: i32, y: i32) -> Option<Vec<BoardVec>> {
+    self.open(BoardVec::new(x, y))
   }
 
-  // todo: better tip 
-  pub fn tipp(&self) -> Vec<BoardVec> {
-    let state = State::from(self);
+  pub fn is_visible_at(&self, x: i32, y: i32) -> bool {
+    self.is_visible(BoardVec::new(x, y))
+  }
 
-    let mut suggestions: Vec<BoardVec> = state.suggestions().collect();
-    if suggestions.is_empty() {
-      suggestions = state.deep_suggestion();
-    }
-    suggestions
+  pub fn flag_at(&mut self, x: i32, y: i32) -> bool {
+    self.flag(BoardVec::new(x, y))
   }
 
-  pub fn is_solvable(mut self) -> bool {
-    let mut state = State::from(&self);
-    loop {
-      if self.is_win() {
-        return true;
-      }
+  pub fn view_at(&self, x: i32, y: i32) -> Option<Field> {
+    self.view(BoardVec::new(x, y))
+  }
 
-      let mut suggestions = state.suggestions().collect::<Vec<_>>();
-      if suggestions.is_empty() {
-        suggestions = state.deep_suggestion();
-        if suggestions.is_empty() {
-          return false;
+  /// The glyph a player sees at `pos`: the field's number, ` ` for blank, `⚑` for flagged and `░` for hidden.
+  pub fn render_char(&self, pos: BoardVec) -> char {
+    match self.view(pos) {
+      Some(Field::Mine) => 'X',
+      Some(Field::Empty(0)) => ' ',
+      Some(Field::Empty(mines)) => char::from_digit(mines, 10).unwrap_or('+'),
+      None if self.is_flagged(pos) => '⚑',
+      None => '░',
+    }
+  }
+
+  /// Hashes only what the player can see, so games differing in hidden mines hash equally.
+  /// Uses FNV-1a over a fixed encoding, so the hash is the same on every platform and Rust release.
+  pub fn view_hash(&self) -> u64 {
+    let mut hash = fnv1a(FNV_OFFSET_BASIS, &self.width().to_le_bytes());
+    hash = fnv1a(hash, &self.height().to_le_bytes());
+    for pos in self.board().positions() {
+      hash = match self.view(pos) {
+        None => fnv1a(hash, &[0]),
+        Some(Field::Mine) => fnv1a(hash, &[1]),
+        Some(Field::Empty(mines)) => fnv1a(fnv1a(hash, &[2]), &mines.to_le_bytes()),
+      };
+    }
+    hash
+  }
+
+  pub fn is_flagged(&self, pos: BoardVec) -> bool {
+    self.flagged[pos]
+  }
+
+  pub fn flag(&mut self, pos: BoardVec) -> bool {
+    if self.is_visible(pos) || self.is_flagged(pos) {
+      return false;
+    }
+    self.flagged[pos] = true;
+    *self.flag_counter(pos) += 1;
+    self.notify(GameEvent::Flagged(pos));
+    if self.win_condition == WinCondition::FlagAllMines && self.is_win() {
+      self.notify(GameEvent::Won);
+    }
+    true
+  }
+
+  pub fn unflag(&mut self, pos: BoardVec) -> bool {
+    let was_flagged = std::mem::replace(&mut self.flagged[pos], false);
+    if was_flagged {
+      *self.flag_counter(pos) -= 1;
+    }
+    was_flagged
+  }
+
+  fn flag_counter(&mut self, pos: BoardVec) -> &mut u32 {
+    if self.board()[pos].is_mine() {
+      &mut self.flagged_mines
+    } else {
+      &mut self.flagged_safe
+    }
+  }
+
+  pub fn flags(&self) -> impl Iterator<Item = BoardVec> + '_ {
+    self.board().positions().filter(|&pos| self.is_flagged(pos))
+  }
+
+  /// Open cells with more flagged neighbours than their number.
+  pub fn flag_conflicts(&self) -> Vec<BoardVec> {
+    self
+      .board()
+      .positions()
+      .filter(|&pos| matches!(self.cell_summary(pos), Some(summary) if summary.flagged > summary.number))
+      .collect()
+  }
+
+  /// Counts the neighbours of an open cell. Flagged neighbours are not counted as `hidden`.
+  pub fn cell_summary(&self, pos: BoardVec) -> Option<CellSummary> {
+    let number = match self.view(pos)? {
+      Field::Empty(number) => number,
+      Field::Mine => return None,
+    };
+
+    let mut summary = CellSummary {
+      number,
+      hidden: 0,
+      flagged: 0,
+      opened: 0,
+    };
+    for neighbour_pos in pos.neighbours_in_radius(self.setup.radius) {
+      match self.view.get(neighbour_pos) {
+        Some(true) => summary.opened += 1,
+        Some(false) if self.is_flagged(neighbour_pos) => summary.flagged += 1,
+        Some(false) => summary.hidden += 1,
+        None => (),
+      }
+    }
+
+    Some(summary)
+  }
+
+  /// Called with every event of this game, from within `open` and `flag`.
+  pub fn set_observer(&mut self, f: Box<dyn FnMut(GameEvent) + Send>) {
+    self.observer = Observer(Some(f));
+  }
+
+  fn notify(&mut self, event: GameEvent) {
+    if let Some(observer) = &mut self.observer.0 {
+      observer(event);
+    }
+  }
+
+  fn hit_mine(&mut self, pos: BoardVec) {
+    self.lost = true;
+    self.notify(GameEvent::MineHit(pos));
+    self.notify(GameEvent::Lost);
+  }
+
+  /// Opens a single cell, removing its flag.
+  fn reveal(&mut self, pos: BoardVec) {
+    self.unflag(pos);
+    self.view[pos] = true;
+    self.hidden_fields -= 1;
+    debug_assert!(self.hidden_fields >= self.setup.mines);
+    self.notify(GameEvent::CellOpened(pos));
+    if self.win_condition == WinCondition::OpenAllSafe && self.is_win() {
+      self.notify(GameEvent::Won);
+    }
+  }
+
+  /// Opens `pos` and, if it is blank, its surrounding area. The flood stops at flagged cells, but a flag on `pos`
+  /// itself is removed. Opening an already open number chords it, an already open blank field opens nothing.
+  pub fn open(&mut self, pos: BoardVec) -> Option<Vec<BoardVec>> {
+    if self.is_visible(pos) && !self.board()[pos].is_blank() {
+      return self.chord(pos);
+    }
+    self.open_rings(pos).map(|rings| rings.concat())
+  }
+
+  /// Like `open`, but groups the opened cells by their flood distance from `pos`:
+  /// `rings[0]` is `pos` itself, `rings[1]` the cells opened from it and so on. Never chords.
+  pub fn open_rings(&mut self, pos: BoardVec) -> Option<Vec<Vec<BoardVec>>> {
+    if self.is_visible(pos) {
+      return Some(Vec::new());
+    }
+    if self.board()[pos].is_mine() {
+      self.hit_mine(pos);
+      return None;
+    }
+
+    let persistent = self.explorer.is_some();
+    let mut explorer = self
+      .explorer
+      .take()
+      .unwrap_or_else(|| BoardExplorer::from(self.board()));
+    explorer.enqueue(pos);
+
+    let mut rings = Vec::new();
+    while explorer.queued() > 0 {
+      let mut ring = Vec::new();
+      for _ in 0..explorer.queued() {
+        let pos = explorer.pop().expect("counted as queued");
+        if !self.is_visible(pos) {
+          self.reveal(pos);
+          ring.push(pos);
+          if self.board()[pos].is_blank() {
+            explorer.enqueue_all(self.unflagged_neighbours(pos));
+          }
+        }
+      }
+      if !ring.is_empty() {
+        rings.push(ring);
+      }
+    }
+
+    if persistent {
+      self.explorer = Some(explorer);
+    }
+    Some(rings)
+  }
+
+  /// Like `open`, but stops the flood after `max_cells` cells. The rest is opened by `resume_open`.
+  /// Never chords.
+  pub fn open_limited(&mut self, pos: BoardVec, max_cells: Option<usize>) -> Option<LimitedOpen> {
+    if self.board()[pos].is_mine() && !self.is_visible(pos) {
+      self.hit_mine(pos);
+      return None;
+    }
+    if !self.is_visible(pos) {
+      self.unflag(pos);
+    }
+    self.pending.push(pos);
+    Some(self.resume_open(max_cells))
+  }
+
+  /// Continues the floods interrupted by `open_limited`, opening at most `max_cells` cells.
+  pub fn resume_open(&mut self, max_cells: Option<usize>) -> LimitedOpen {
+    let max_cells = max_cells.unwrap_or(usize::MAX);
+    let mut explorer = BoardExplorer::from(self.board());
+    let pending: Vec<BoardVec> = self.pending.drain(..).collect();
+    explorer.enqueue_all(pending.into_iter().filter(|&pos| !self.is_flagged(pos)));
+
+    let mut opened = Vec::new();
+    while opened.len() < max_cells {
+      let pos = match explorer.pop() {
+        Some(pos) => pos,
+        None => break,
+      };
+      if !self.is_visible(pos) {
+        self.reveal(pos);
+        opened.push(pos);
+        if self.board()[pos].is_blank() {
+          explorer.enqueue_all(self.unflagged_neighbours(pos));
         }
       }
+    }
+
+    // Everything still queued is a hidden neighbour of an opened blank cell.
+    self.pending.extend(std::iter::from_fn(|| explorer.pop()));
+    self.pending.retain(|&pos| !self.view[pos]);
+    LimitedOpen {
+      opened,
+      pending: !self.pending.is_empty(),
+    }
+  }
+
+  /// The neighbours a flood continues to from the blank cell at `pos`.
+  fn unflagged_neighbours(&self, pos: BoardVec) -> impl Iterator<Item = BoardVec> + '_ {
+    pos
+      .neighbours_in_radius(self.setup.radius)
+      .filter(move |&n| !self.flagged.get(n).copied().unwrap_or(false))
+  }
+
+  /// Opens all hidden, unflagged neighbours of an open number once as many of its neighbours are flagged as its
+  /// number says.
+  pub fn chord(&mut self, pos: BoardVec) -> Option<Vec<BoardVec>> {
+    let mut opened = Vec::new();
+    match self.cell_summary(pos) {
+      Some(summary) if summary.flagged == summary.number => (),
+      _ => return Some(opened),
+    }
+
+    for neighbour_pos in pos.neighbours_in_radius(self.setup.radius) {
+      if self.view.get(neighbour_pos) == Some(&false) && !self.is_flagged(neighbour_pos) {
+        opened.extend(self.open(neighbour_pos)?);
+      }
+    }
+    Some(opened)
+  }
+
+  /// Like `open`, but without `propagate` only `pos` itself is revealed, even if it is blank.
+  pub fn force_open(&mut self, pos: BoardVec, propagate: bool) -> Option<Vec<BoardVec>> {
+    if propagate {
+      return self.open(pos);
+    }
+
+    if self.board()[pos].is_mine() {
+      self.hit_mine(pos);
+      return None;
+    }
+    if self.is_visible(pos) {
+      return Some(Vec::new());
+    }
+
+    self.reveal(pos);
+    Some(vec![pos])
+  }
+
+  /// Hides all cells and removes all flags, keeping the mine layout so the same puzzle can be replayed.
+  pub fn restart(&mut self) {
+    self.view.iter_mut().for_each(|visible| *visible = false);
+    self.flagged.iter_mut().for_each(|flagged| *flagged = false);
+    self.flagged_mines = 0;
+    self.flagged_safe = 0;
+    self.hidden_fields = self.board().len() as u32;
+    self.lost = false;
+    self.pending.clear();
+    if let Some(explorer) = &mut self.explorer {
+      explorer.reset();
+    }
+  }
+
+  /// Makes all mines visible after a loss, split into the flagged and the missed ones.
+  /// `hidden_fields` is left alone, as the game is over anyway.
+  pub fn reveal_mines(&mut self) -> RevealedMines {
+    assert!(self.is_lost(), "Mines can only be revealed after losing");
+
+    let mut revealed = RevealedMines::default();
+    for pos in self.setup.board.positions() {
+      if self.board()[pos].is_mine() {
+        self.view[pos] = true;
+        if self.is_flagged(pos) {
+          revealed.flagged.push(pos);
+        } else {
+          revealed.missed.push(pos);
+        }
+      }
+    }
+    revealed
+  }
+
+  /// Keeps one explorer across all opens, so that no cell is ever walked twice.
+  pub fn set_persistent_explorer(&mut self, persistent: bool) {
+    self.explorer = persistent.then(|| BoardExplorer::with_visited(self.view.clone()));
+  }
+
+  /// Opens `pos` and flags every mine the solver can deduce afterwards.
+  pub fn open_with_autoflag(&mut self, pos: BoardVec) -> OpenOutcome {
+    let opened = match self.open(pos) {
+      Some(opened) => opened,
+      None => return OpenOutcome::HitMine(pos),
+    };
+
+    let state = State::from(&*self);
+    let flagged = state.known_mines().filter(|&mine| self.flag(mine)).collect();
+    OpenOutcome::Opened {
+      opened,
+      flagged,
+      won: self.is_win(),
+    }
+  }
+
+  // todo: better tip
+  pub fn tipp(&self) -> Vec<BoardVec> {
+    let state = State::from(self);
+
+    let mut suggestions: Vec<BoardVec> = state.suggestions().collect();
+    if suggestions.is_empty() {
+      suggestions = state.deduced_safe();
+    }
+    if suggestions.is_empty() {
+      suggestions = state.deep_suggestion();
+    }
+    suggestions
+  }
+
+  /// Suggests a single move, preferring safe cells over flagging mines over the least risky guess.
+  pub fn hint(&self) -> Option<Hint> {
+    if self.is_win() || self.is_lost() {
+      return None;
+    }
+
+    let state = State::from(self);
+    if let Some(pos) = state.suggestions().next() {
+      return Some(Hint::OpenSafe(pos));
+    }
+    if let Some(pos) = state.known_mines().find(|&pos| !self.is_flagged(pos)) {
+      return Some(Hint::FlagMine(pos));
+    }
+
+    let probabilities = state.mine_probabilities();
+    let hidden: Vec<BoardVec> = self.board().positions().filter(|&pos| !self.is_visible(pos)).collect();
+    if let Some(&pos) = hidden.iter().find(|&&pos| probabilities[pos] == 0.0) {
+      return Some(Hint::OpenSafe(pos));
+    }
+    if let Some(&pos) = hidden
+      .iter()
+      .find(|&&pos| probabilities[pos] == 1.0 && !self.is_flagged(pos))
+    {
+      return Some(Hint::FlagMine(pos));
+    }
+
+    hidden
+      .into_iter()
+      .filter(|&pos| probabilities[pos] < 1.0)
+      .min_by(|&a, &b| probabilities[a].total_cmp(&probabilities[b]))
+      .map(|pos| Hint::Guess {
+        pos,
+        probability: probabilities[pos],
+      })
+  }
+
+  /// Baseline player that opens uniformly random hidden cells until the game is won or lost.
+  pub fn play_random(&mut self, rng: &mut impl RngCore) -> bool {
+    while !self.is_win() && !self.is_lost() {
+      let hidden: Vec<BoardVec> = self.board().positions().filter(|&pos| !self.is_visible(pos)).collect();
+      let &pos = hidden.choose(rng).unwrap();
+      self.open(pos);
+    }
+    self.is_win()
+  }
+
+  pub fn is_solvable(mut self) -> bool {
+    self.autoplay(true).outcome == AutoplayOutcome::Won
+  }
+
+  /// Plays until won, lost or stuck. `deep_suggestion` is only used with `allow_guessing`.
+  pub fn autoplay(&mut self, allow_guessing: bool) -> AutoplayResult {
+    self.play(allow_guessing, None)
+  }
+
+  /// Like `autoplay(true)`, but asks `heuristic` for a cell to open whenever nothing can be deduced.
+  pub fn autoplay_with(&mut self, heuristic: &mut dyn GuessHeuristic) -> AutoplayResult {
+    self.play(true, Some(heuristic))
+  }
+
+  fn play(&mut self, allow_guessing: bool, mut heuristic: Option<&mut dyn GuessHeuristic>) -> AutoplayResult {
+    let mut state = State::from(&*self);
+    let mut moves = 0;
+    loop {
+      if self.is_lost() {
+        return AutoplayResult {
+          outcome: AutoplayOutcome::Lost,
+          moves,
+        };
+      }
+
+      if self.is_win() {
+        return AutoplayResult {
+          outcome: AutoplayOutcome::Won,
+          moves,
+        };
+      }
+
+      for mine in state.known_mines() {
+        if self.flag(mine) {
+          moves += 1;
+        }
+      }
+
+      let mut suggestions = state.suggestions().collect::<Vec<_>>();
+      if suggestions.is_empty() && allow_guessing {
+        suggestions = state.deep_suggestion();
+      }
+      if suggestions.is_empty() {
+        if let Some(heuristic) = &mut heuristic {
+          suggestions.extend(heuristic.guess(&state));
+        }
+      }
+
+      if suggestions.is_empty() {
+        return AutoplayResult {
+          outcome: AutoplayOutcome::Stuck,
+          moves,
+        };
+      }
 
       let mut mutator = state.into_mutator();
       for suggestion in suggestions {
-        for opened in self.open(suggestion).unwrap() {
+        if self.is_visible(suggestion) {
+          continue;
+        }
+        moves += 1;
+        for opened in self.open(suggestion).into_iter().flatten() {
           mutator.mark_explored(opened, self.view(opened).unwrap())
         }
       }
@@ -267,34 +1421,106 @@ impl Game {
   }
 }
 
+/// Number of setups that are solvable when opened at `start`, checked in parallel.
+#[cfg(feature = "parallel")]
+pub fn count_solvable(setups: &[GameSetup], start: BoardVec) -> usize {
+  setups
+    .par_iter()
+    .filter(|setup| {
+      let mut game = Game::from((*setup).clone());
+      game.open(start);
+      game.is_solvable()
+    })
+    .count()
+}
+
+/// Indices of the setups that `a` and `b` judge differently when opened at `start`.
+pub fn solvers_agree(setups: &[GameSetup], start: BoardVec, a: &dyn Solver, b: &dyn Solver) -> Vec<usize> {
+  setups
+    .iter()
+    .enumerate()
+    .filter(|(_, setup)| {
+      let mut game = Game::from((*setup).clone());
+      game.open(start);
+      a.is_solvable(game.clone()) != b.is_solvable(game)
+    })
+    .map(|(i, _)| i)
+    .collect()
+}
+
+/// Fraction of `samples` random games won by `autoplay_with(heuristic)`, each opened at the center.
+/// Panics if `samples` is 0, as there is no rate to estimate then.
+pub fn estimate_win_rate(
+  width: u32,
+  height: u32,
+  mines: u32,
+  samples: u32,
+  seed: u64,
+  heuristic: &mut dyn GuessHeuristic,
+) -> f64 {
+  assert!(samples > 0, "Cannot estimate the win rate from 0 samples");
+  let mut rng = StdRng::seed_from_u64(seed);
+  let start = BoardVec::new((width / 2) as i32, (height / 2) as i32);
+  let mut won = 0;
+  for _ in 0..samples {
+    let board_rng = StdRng::from_rng(&mut rng).unwrap();
+    let mut builder = GameSetupBuilder::with_rng(width, height, Box::new(board_rng));
+    builder.protect_start(start, FirstClickPolicy::ThreeByThree);
+    if !builder.add_random_mines(mines) {
+      panic!("Cannot place {} mines on a {}x{} board", mines, width, height);
+    }
+
+    let mut game = Game::from(builder);
+    game.open(start);
+    if game.autoplay_with(heuristic).outcome == AutoplayOutcome::Won {
+      won += 1;
+    }
+  }
+  won as f64 / samples as f64
+}
+
 impl From<GameSetup> for Game {
   fn from(setup: GameSetup) -> Self {
     Self {
-      view: ViewBoard::new(setup.width(), setup.height(), false),
-      hidden_fields: setup.width() * setup.height(),
+      view: setup.board.map(|_| false),
+      flagged: setup.board.map(|_| false),
+      hidden_fields: setup.board.len() as u32,
+      win_condition: WinCondition::default(),
+      flagged_mines: 0,
+      flagged_safe: 0,
+      lost: false,
+      explorer: None,
+      pending: Vec::new(),
+      observer: Observer::default(),
       setup,
     }
   }
 }
 
+/// A game with the builder's pre-opened cells already open.
 impl<B: Borrow<GameSetupBuilder>> From<B> for Game {
-  fn from(setup: B) -> Self {
-    Self::from(GameSetup::from(setup))
+  fn from(builder: B) -> Self {
+    let builder: &GameSetupBuilder = builder.borrow();
+    let mut game = Self::from(GameSetup::from(builder));
+    for &pos in &builder.pre_opened {
+      if !game.board()[pos].is_mine() {
+        game.open_rings(pos);
+      }
+    }
+    game
   }
 }
 
 impl fmt::Debug for Game {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    for y in 0..self.height() {
-      for x in 0..self.width() {
-        let pos = BoardVec::new(x as i32, y as i32);
-        if self.is_visible(pos) {
-          write!(f, "{}", self.board()[pos])?;
-        } else {
-          write!(f, "░")?;
-        }
+    if f.alternate() {
+      return write_annotated(f, self.board(), self.setup.mines, |pos| self.render_char(pos));
+    }
+    for pos in self.board().positions() {
+      write!(f, "{}", self.render_char(pos))?;
+      if pos.x + 1 == self.board().origin.x + self.width() as i32 {
+        writeln!(f)?;
       }
-      writeln!(f)?;
     }
 
     Ok(())
@@ -322,3 +1548,1033 @@ impl FieldView {
     self == FieldView::Flagged
   }
 }*/
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{Arc, Mutex};
+
+  use super::*;
+
+  fn setup(rows: &[&str]) -> GameSetup {
+    GameSetup::from_reader(rows.join("\n").as_bytes()).unwrap()
+  }
+
+  fn mine_counts(rows: &[&[u32]]) -> Board<u32> {
+    let fields = rows.iter().flat_map(|row| row.iter().copied()).collect();
+    Board::from_flat(rows[0].len() as u32, rows.len() as u32, fields).unwrap()
+  }
+
+  #[test]
+  fn double_mine_cell_counts_twice() {
+    let setup = GameSetup::from_mine_counts(&mine_counts(&[&[2, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 1]]), 2);
+    assert_eq!(setup.board[BoardVec::new(1, 0)], Field::Empty(2));
+    assert_eq!(setup.board[BoardVec::new(1, 1)], Field::Empty(2));
+    assert_eq!(setup.board[BoardVec::new(2, 2)], Field::Empty(1));
+    assert_eq!(
+      (setup.mines, setup.total_mines(), setup.max_mines_per_cell()),
+      (2, 3, 2)
+    );
+
+    let mut game = Game::from(setup);
+    assert_eq!(game.open(BoardVec::new(0, 0)), None);
+    assert!(game.is_lost());
+  }
+
+  #[test]
+  fn autoplay_solves_double_mine_board() {
+    let setup = GameSetup::from_mine_counts(&mine_counts(&[&[2, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 1]]), 2);
+    let mut game = Game::from(setup);
+    game.open(BoardVec::new(2, 0));
+    assert_eq!(game.autoplay(true).outcome, AutoplayOutcome::Won);
+  }
+
+  #[test]
+  fn declared_capacity_is_kept_when_no_cell_uses_it() {
+    // The 2 could also be a double mine on either side, so nothing follows from it.
+    let counts = mine_counts(&[&[1, 0, 1]]);
+    let mut game = Game::from(GameSetup::from_mine_counts(&counts, 2));
+    assert_eq!(game.setup().max_mines_per_cell(), 2);
+    game.open(BoardVec::new(1, 0));
+    let state = State::from(&game);
+    assert_eq!(*state.knowledge_at(BoardVec::new(0, 0)), FieldKnowledge::Unknown);
+    assert_eq!(*state.knowledge_at(BoardVec::new(2, 0)), FieldKnowledge::Unknown);
+
+    let mut game = Game::from(GameSetup::from_mine_counts(&counts, 1));
+    game.open(BoardVec::new(1, 0));
+    let state = State::from(&game);
+    assert_eq!(*state.knowledge_at(BoardVec::new(0, 0)), FieldKnowledge::Mine);
+    assert_eq!(*state.knowledge_at(BoardVec::new(2, 0)), FieldKnowledge::Mine);
+  }
+
+  #[test]
+  #[should_panic]
+  fn mine_counts_above_the_capacity() {
+    GameSetup::from_mine_counts(&mine_counts(&[&[2, 0, 0]]), 1);
+  }
+
+  #[test]
+  #[should_panic]
+  fn builder_refuses_mines_above_the_capacity() {
+    let mut builder = GameSetupBuilder::new(3, 3);
+    builder.add_mine(BoardVec::new(1, 1));
+    builder.add_mine(BoardVec::new(1, 1));
+  }
+
+  #[test]
+  fn game_is_send() {
+    fn assert_send<T: Send>() {}
+    assert_send::<Game>();
+  }
+
+  #[test]
+  fn observer_sees_events_of_the_original_only() {
+    let mut game = Game::from(setup(&["..", ".*"]));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink = events.clone();
+    game.set_observer(Box::new(move |event| sink.lock().unwrap().push(event)));
+
+    game.flag(BoardVec::new(1, 1));
+    game.open(BoardVec::new(0, 0));
+    game.open(BoardVec::new(1, 0));
+    game.open(BoardVec::new(0, 1));
+    let mut clone = game.clone();
+    clone.open(BoardVec::new(1, 1));
+    game.open(BoardVec::new(1, 1));
+
+    assert_eq!(
+      *events.lock().unwrap(),
+      vec![
+        GameEvent::Flagged(BoardVec::new(1, 1)),
+        GameEvent::CellOpened(BoardVec::new(0, 0)),
+        GameEvent::CellOpened(BoardVec::new(1, 0)),
+        GameEvent::CellOpened(BoardVec::new(0, 1)),
+        GameEvent::Won,
+        GameEvent::MineHit(BoardVec::new(1, 1)),
+        GameEvent::Lost,
+      ]
+    );
+  }
+
+  #[test]
+  fn setup_and_game_keep_the_origin() {
+    let mut bombs = Board::new(3, 3, false).with_origin(BoardVec::new(-1, -1));
+    bombs[BoardVec::new(1, 1)] = true;
+    let setup = GameSetup::new(&bombs);
+    assert_eq!(setup.board[BoardVec::new(1, 1)], Field::Mine);
+    assert_eq!(setup.board[BoardVec::new(0, 0)], Field::Empty(1));
+    assert_eq!(setup.board[BoardVec::new(-1, -1)], Field::Empty(0));
+    assert_eq!(format!("{:?}", setup), "   \n 11\n 1X\n");
+
+    let mut game = Game::from(setup);
+    assert_eq!(game.open(BoardVec::new(-1, -1)).map(|opened| opened.len()), Some(8));
+    assert!(game.is_win());
+    assert_eq!(format!("{:?}", game), "   \n 11\n 1░\n");
+    assert_eq!(format!("{:#?}", game), "3x3, 1 mines\n   901\n-1    \n 0  11\n 1  1░\n");
+  }
+
+  #[test]
+  fn autoplay_on_origin_board() {
+    let mut bombs = Board::new(4, 3, false).with_origin(BoardVec::new(-5, -5));
+    bombs[BoardVec::new(-2, -3)] = true;
+    let mut game = Game::from(GameSetup::new(&bombs));
+    game.open(BoardVec::new(-5, -5));
+    assert_eq!(game.autoplay(false).outcome, AutoplayOutcome::Won);
+  }
+
+  #[test]
+  fn largest_open_region_on_origin_board() {
+    let mut bombs = Board::new(4, 3, false).with_origin(BoardVec::new(-5, -5));
+    bombs[BoardVec::new(-2, -3)] = true;
+    assert_eq!(GameSetup::new(&bombs).largest_open_region(), 11);
+  }
+
+  #[test]
+  fn from_reader_round_trips_debug_output() {
+    let mut bombs = Board::new(5, 4, false);
+    bombs[BoardVec::new(0, 0)] = true;
+    let setup = GameSetup::new(&bombs);
+    let text = format!("{:?}", setup);
+    assert_eq!(text, "X1   \n11   \n     \n     \n");
+    assert_eq!(GameSetup::from_reader(text.as_bytes()).unwrap(), setup);
+  }
+
+  #[test]
+  fn from_reader_accepts_crlf_and_trailing_empty_lines() {
+    let setup = GameSetup::from_reader(io::Cursor::new(b"*..\r\n...\r\n\n".to_vec())).unwrap();
+    assert_eq!((setup.width(), setup.height(), setup.mines), (3, 2, 1));
+    assert_eq!(setup.board[BoardVec::new(1, 1)], Field::Empty(1));
+  }
+
+  #[test]
+  fn from_reader_rejects_ragged_rows() {
+    let err = GameSetup::from_reader(&b"*..\n..\n"[..]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    assert!(GameSetup::from_reader(&b"*..\n\n...\n"[..]).is_err());
+    assert!(GameSetup::from_reader(&b"*.?\n"[..]).is_err());
+    assert!(GameSetup::from_reader(&b"\n\n"[..]).is_err());
+  }
+
+  #[test]
+  fn from_reader_ignores_trailing_whitespace() {
+    let text = b"*.. \t\r\n...  \n.1.\t\n\n".to_vec();
+    let setup = GameSetup::from_reader(io::Cursor::new(text)).unwrap();
+    assert_eq!(setup, GameSetup::from_reader(&b"*..\n...\n...\n"[..]).unwrap());
+    assert!(GameSetup::from_reader(&b"*.. \n..\n"[..]).is_err());
+  }
+
+  #[test]
+  fn flood_stops_at_flags() {
+    let mut game = Game::from(setup(&["....", "....", "...*"]));
+    game.flag(BoardVec::new(1, 1));
+    let opened = game.open(BoardVec::new(0, 0)).unwrap();
+    assert_eq!(opened.len(), 10);
+    assert!(!game.is_visible(BoardVec::new(1, 1)));
+    assert_eq!(game.flags().collect::<Vec<_>>(), vec![BoardVec::new(1, 1)]);
+    assert!(!game.is_win());
+
+    game.open(BoardVec::new(1, 1));
+    assert!(game.is_visible(BoardVec::new(1, 1)));
+    assert_eq!(game.flags().count(), 0);
+    assert!(game.is_win());
+  }
+
+  #[test]
+  fn flood_keeps_flag_accounting() {
+    let mut game = Game::from(setup(&["....", "....", "...*"]));
+    game.set_win_condition(WinCondition::FlagAllMines);
+    game.flag(BoardVec::new(3, 0));
+    game.open(BoardVec::new(0, 0));
+    assert_eq!((game.flagged_mines, game.flagged_safe), (0, 1));
+    game.open(BoardVec::new(3, 0));
+    assert_eq!((game.flagged_mines, game.flagged_safe), (0, 0));
+    game.flag(BoardVec::new(3, 2));
+    assert!(game.is_win());
+  }
+
+  #[test]
+  fn open_rings_skips_flags() {
+    let mut game = Game::from(setup(&[".....", "....*"]));
+    game.set_win_condition(WinCondition::FlagAllMines);
+    game.flag(BoardVec::new(1, 0));
+    game.flag(BoardVec::new(4, 1));
+    let rings = game.open_rings(BoardVec::new(0, 0)).unwrap();
+    assert_eq!(
+      rings,
+      vec![
+        vec![BoardVec::new(0, 0)],
+        vec![BoardVec::new(0, 1), BoardVec::new(1, 1)],
+        vec![BoardVec::new(2, 0), BoardVec::new(2, 1)],
+        vec![BoardVec::new(3, 0), BoardVec::new(3, 1)],
+      ]
+    );
+    assert!(!game.is_visible(BoardVec::new(1, 0)));
+    assert!(!game.is_win());
+
+    game.unflag(BoardVec::new(1, 0));
+    assert!(game.is_win());
+  }
+
+  #[test]
+  fn radius_two_counts_24_neighbours() {
+    let mut bombs = Board::new(7, 7, false);
+    bombs[BoardVec::new(3, 3)] = true;
+    let setup = GameSetup::with_radius(&bombs, 2);
+    let counted = setup.board.iter().filter(|&&field| field == Field::Empty(1)).count();
+    assert_eq!(counted, 24);
+    assert_eq!(setup.board[BoardVec::new(0, 0)], Field::Empty(0));
+  }
+
+  #[test]
+  #[should_panic(expected = "Radius must be at least 1")]
+  fn radius_zero_is_rejected() {
+    GameSetupBuilder::new(3, 3).set_radius(0);
+  }
+
+  #[test]
+  #[should_panic(expected = "Radius must be at least 1")]
+  fn negative_radius_is_rejected() {
+    GameSetup::with_radius(&Board::new(3, 3, false), -1);
+  }
+
+  fn no_guess_game(
+    size: (u32, u32),
+    mines: u32,
+    start: BoardVec,
+    policy: FirstClickPolicy,
+    seed: u64,
+  ) -> Option<Game> {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    GameSetupBuilder::new(size.0, size.1).generate_no_guess(mines, start, policy, &mut rng)
+  }
+
+  #[test]
+  fn generate_no_guess_is_solvable_without_guessing() {
+    let game = no_guess_game((12, 8), 18, BoardVec::new(6, 4), FirstClickPolicy::CellOnly, 5).unwrap();
+    assert_eq!(game.setup().total_mines(), 18);
+    assert!(!game.board()[BoardVec::new(6, 4)].is_mine());
+    assert_eq!(game.clone().autoplay(false).outcome, AutoplayOutcome::Won);
+  }
+
+  #[test]
+  fn generate_no_guess_backtracks_on_dense_boards() {
+    // With this seed, placing mines greedily runs out of candidates.
+    let game = no_guess_game((5, 5), 8, BoardVec::new(2, 2), FirstClickPolicy::CellOnly, 5).unwrap();
+    assert_eq!(game.setup().total_mines(), 8);
+    assert_eq!(game.clone().autoplay(false).outcome, AutoplayOutcome::Won);
+  }
+
+  #[test]
+  fn generate_no_guess_fails_without_room() {
+    assert!(no_guess_game((3, 3), 9, BoardVec::new(1, 1), FirstClickPolicy::CellOnly, 0).is_none());
+  }
+
+  #[test]
+  fn estimate_win_rate_is_reproducible() {
+    use solver::guess::LowestProbability;
+
+    assert_eq!(estimate_win_rate(8, 8, 0, 5, 1, &mut LowestProbability), 1.0);
+    let rate = estimate_win_rate(9, 9, 10, 30, 7, &mut LowestProbability);
+    assert_eq!(rate, estimate_win_rate(9, 9, 10, 30, 7, &mut LowestProbability));
+    assert!(rate > 0.5);
+  }
+
+  #[test]
+  #[should_panic(expected = "Cannot estimate the win rate from 0 samples")]
+  fn estimate_win_rate_needs_samples() {
+    estimate_win_rate(8, 8, 10, 0, 1, &mut solver::guess::LowestProbability);
+  }
+
+  #[test]
+  fn autoplay_wins_deducible_board_and_flags_mines() {
+    let mut game = Game::from(setup(&["..*.."]));
+    game.open(BoardVec::new(0, 0));
+    assert!(!game.is_win());
+    let result = game.autoplay(false);
+    assert_eq!(result.outcome, AutoplayOutcome::Won);
+    assert!(game.is_flagged(BoardVec::new(2, 0)));
+    assert!(result.moves >= 1);
+  }
+
+  #[test]
+  fn autoplay_gets_stuck_without_guessing() {
+    let mut game = Game::from(setup(&["..", "..", "*."]));
+    game.open(BoardVec::new(0, 0));
+    assert_eq!(game.autoplay(false).outcome, AutoplayOutcome::Stuck);
+    assert!(!game.is_lost());
+  }
+
+  #[test]
+  fn autoplay_reports_a_lost_game() {
+    let mut game = Game::from(setup(&["*.", ".."]));
+    game.open(BoardVec::new(0, 0));
+    assert_eq!(
+      game.autoplay(true),
+      AutoplayResult {
+        outcome: AutoplayOutcome::Lost,
+        moves: 0
+      }
+    );
+  }
+
+  #[test]
+  fn weighted_mines_follow_the_weights() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut weights = Board::new(10, 10, 0.0);
+    for pos in weights.positions().filter(|pos| pos.x < 5 && pos.y < 5) {
+      weights[pos] = 1.0;
+    }
+    weights[BoardVec::new(9, 9)] = 0.01;
+
+    let mut builder = GameSetupBuilder::with_rng(10, 10, Box::new(StdRng::seed_from_u64(1)));
+    builder.protect_all([BoardVec::new(0, 0)]);
+    assert!(builder.add_weighted_mines(20, &weights));
+    let placed: Vec<BoardVec> = builder.mines.positions().filter(|&pos| builder.has_mine(pos)).collect();
+    assert_eq!(placed.len(), 20);
+    assert!(placed.iter().filter(|pos| pos.x < 5 && pos.y < 5).count() >= 19);
+    assert!(placed
+      .iter()
+      .all(|&pos| weights[pos] > 0.0 && pos != BoardVec::new(0, 0)));
+
+    // Only 5 cells with a weight are left.
+    assert!(!builder.add_weighted_mines(6, &weights));
+  }
+
+  #[test]
+  fn cell_summary_counts_the_players_view() {
+    let mut game = Game::from(setup(&["..", "..", "*."]));
+    game.open(BoardVec::new(0, 0));
+    game.flag(BoardVec::new(0, 2));
+    assert_eq!(
+      game.cell_summary(BoardVec::new(0, 1)),
+      Some(CellSummary {
+        number: 1,
+        hidden: 1,
+        flagged: 1,
+        opened: 3
+      })
+    );
+    assert_eq!(game.cell_summary(BoardVec::new(0, 2)), None);
+    assert_eq!(game.cell_summary(BoardVec::new(1, 2)), None);
+  }
+
+  #[test]
+  fn builder_stacks_mines() {
+    let mut builder = GameSetupBuilder::new(3, 3);
+    builder.set_max_mines_per_cell(2);
+    builder.add_mine(BoardVec::new(1, 1));
+    builder.add_mine(BoardVec::new(1, 1));
+    builder.set_mine(BoardVec::new(0, 0));
+    let game = Game::from(&builder);
+    assert_eq!(game.board()[BoardVec::new(2, 2)], Field::Empty(2));
+    assert_eq!(game.board()[BoardVec::new(1, 0)], Field::Empty(3));
+    assert_eq!(game.setup().total_mines(), 3);
+  }
+
+  fn hint_after(rows: &[&str], start: BoardVec) -> Option<Hint> {
+    let mut game = Game::from(setup(rows));
+    game.open(start);
+    game.hint()
+  }
+
+  #[test]
+  fn hint_prefers_safe_cells_then_mines_then_guesses() {
+    assert_eq!(
+      hint_after(&["....", "....", "*..*"], BoardVec::new(1, 0)),
+      Some(Hint::OpenSafe(BoardVec::new(1, 2)))
+    );
+
+    let mut game = Game::from(setup(&[".....", "....*", "**...", ".*..."]));
+    game.open(BoardVec::new(0, 0));
+    while let Some(Hint::OpenSafe(pos)) = game.hint() {
+      game.open(pos);
+    }
+    assert_eq!(game.hint(), Some(Hint::FlagMine(BoardVec::new(0, 2))));
+    game.flag(BoardVec::new(0, 2));
+    assert_ne!(game.hint(), Some(Hint::FlagMine(BoardVec::new(0, 2))));
+
+    assert_eq!(
+      hint_after(&["..", "..", "*."], BoardVec::new(0, 0)),
+      Some(Hint::Guess {
+        pos: BoardVec::new(0, 2),
+        probability: 0.5
+      })
+    );
+    assert_eq!(hint_after(&["...", "...", "..*"], BoardVec::new(0, 0)), None);
+  }
+
+  #[test]
+  fn view_hash_ignores_hidden_mines() {
+    let mut a = Game::from(setup(&["...", "***", "*.."]));
+    let mut b = Game::from(setup(&["...", "***", "..*"]));
+    a.open(BoardVec::new(0, 0));
+    b.open(BoardVec::new(0, 0));
+    assert_eq!(a.view_hash(), b.view_hash());
+
+    b.open(BoardVec::new(1, 0));
+    assert_ne!(a.view_hash(), b.view_hash());
+  }
+
+  #[test]
+  fn view_hash_is_stable() {
+    assert_eq!(fnv1a(FNV_OFFSET_BASIS, b"a"), 0xaf63_dc4c_8601_ec8c);
+    let mut game = Game::from(setup(&["...", "***", "*.."]));
+    game.open(BoardVec::new(0, 0));
+    assert_eq!(game.view_hash(), 0xddc1_3bc8_c65b_d4a7);
+  }
+
+  #[test]
+  fn toggle_mine_on_and_off() {
+    let mut builder = GameSetupBuilder::new(3, 3);
+    let pos = BoardVec::new(1, 1);
+    assert!(builder.toggle_mine(pos));
+    assert!(builder.has_mine(pos));
+    assert!(builder.toggle_mine(pos));
+    assert!(!builder.has_mine(pos));
+
+    builder.set_max_mines_per_cell(2);
+    builder.add_mine(pos);
+    builder.add_mine(pos);
+    builder.clear_mine(pos);
+    assert!(!builder.has_mine(pos));
+  }
+
+  #[test]
+  fn toggle_mine_refuses_protected_cells() {
+    let mut builder = GameSetupBuilder::new(3, 3);
+    builder.protect(BoardVec::new(0, 0));
+    assert!(!builder.toggle_mine(BoardVec::new(0, 0)));
+    assert!(!builder.has_mine(BoardVec::new(0, 0)));
+  }
+
+  #[test]
+  fn open_with_autoflag_flags_forced_mines() {
+    let mut game = Game::from(setup(&["..*.."]));
+    assert_eq!(
+      game.open_with_autoflag(BoardVec::new(0, 0)),
+      OpenOutcome::Opened {
+        opened: vec![BoardVec::new(0, 0), BoardVec::new(1, 0)],
+        flagged: vec![BoardVec::new(2, 0)],
+        won: false
+      }
+    );
+    assert!(game.is_flagged(BoardVec::new(2, 0)));
+    assert_eq!(
+      game.open_with_autoflag(BoardVec::new(2, 0)),
+      OpenOutcome::HitMine(BoardVec::new(2, 0))
+    );
+  }
+
+  #[test]
+  fn adjacent_mines_matches_a_neighbour_scan() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let mut builder = GameSetupBuilder::with_rng(9, 7, Box::new(StdRng::seed_from_u64(4)));
+    assert!(builder.add_random_mines(20));
+    let setup = GameSetup::from(&builder);
+    for pos in setup.board.positions() {
+      let scanned = pos
+        .neighbours()
+        .filter(|&n| setup.board.get(n).is_some_and(|field| field.is_mine()))
+        .count() as u32;
+      assert_eq!(setup.adjacent_mines(pos), scanned, "{:?}", pos);
+    }
+    assert_eq!(setup.adjacent_mines(BoardVec::new(-1, 0)), 0);
+  }
+
+  #[test]
+  fn flag_conflicts_reports_over_flagged_numbers() {
+    let mut game = Game::from(setup(&["..*.."]));
+    game.open(BoardVec::new(0, 0));
+    game.flag(BoardVec::new(2, 0));
+    assert_eq!(game.flag_conflicts(), vec![]);
+
+    let mut game = Game::from(setup(&["..", "..", "*."]));
+    game.open(BoardVec::new(0, 0));
+    game.flag(BoardVec::new(0, 2));
+    game.flag(BoardVec::new(1, 2));
+    assert_eq!(
+      game.flags().collect::<Vec<_>>(),
+      vec![BoardVec::new(0, 2), BoardVec::new(1, 2)]
+    );
+    assert_eq!(game.flag_conflicts(), vec![BoardVec::new(0, 1), BoardVec::new(1, 1)]);
+  }
+
+  #[test]
+  fn progress_from_start_to_win() {
+    let mut game = Game::from(setup(&["...*", "****"]));
+    assert_eq!(game.progress(), 0.0);
+    game.open(BoardVec::new(0, 0));
+    assert_eq!(game.progress(), 1.0 / 3.0);
+    game.open(BoardVec::new(1, 0));
+    game.open(BoardVec::new(2, 0));
+    assert!(game.is_win());
+    assert_eq!(game.progress(), 1.0);
+  }
+
+  #[test]
+  fn largest_open_region_counts_the_border_numbers() {
+    assert_eq!(setup(&["....", "....", "*..*"]).largest_open_region(), 8);
+    assert_eq!(setup(&["....*", ".....", "*...."]).largest_open_region(), 13);
+    assert_eq!(setup(&["*.*", "...", "*.*"]).largest_open_region(), 1);
+    assert_eq!(setup(&["**", "**"]).largest_open_region(), 0);
+    // (3, 1) borders the small region in the corner and the large one, and counts for both.
+    assert_eq!(setup(&["..*..", ".....", "....*", "....*"]).largest_open_region(), 14);
+  }
+
+  #[test]
+  fn persistent_explorer_walks_each_cell_once() {
+    // The flag splits the row into two floods. Opening it afterwards borders both of them.
+    let mut game = Game::from(setup(&["......*"]));
+    game.flag(BoardVec::new(2, 0));
+    let mut fresh = game.clone();
+    game.set_persistent_explorer(true);
+
+    let mut opened = Vec::new();
+    for pos in [BoardVec::new(0, 0), BoardVec::new(3, 0), BoardVec::new(2, 0)] {
+      let cells = game.open(pos).unwrap();
+      assert_eq!(Some(cells.clone()), fresh.open(pos));
+      opened.extend(cells);
+    }
+    assert!(game.is_win());
+
+    let explorer = game.explorer.as_ref().unwrap();
+    for pos in game.board().positions() {
+      assert_eq!(explorer.is_visited(pos), game.is_visible(pos), "{:?}", pos);
+      assert!(opened.iter().filter(|&&cell| cell == pos).count() <= 1);
+    }
+  }
+
+  #[test]
+  fn force_open_with_and_without_flood() {
+    let rows = ["....", "....", "*..."];
+    let mut flooded = Game::from(setup(&rows));
+    let mut game = Game::from(setup(&rows));
+    assert_eq!(
+      flooded.force_open(BoardVec::new(3, 0), true),
+      game.clone().open(BoardVec::new(3, 0))
+    );
+
+    assert_eq!(
+      game.force_open(BoardVec::new(3, 0), false),
+      Some(vec![BoardVec::new(3, 0)])
+    );
+    assert_eq!(game.board().positions().filter(|&pos| game.is_visible(pos)).count(), 1);
+    assert_eq!(game.force_open(BoardVec::new(3, 0), false), Some(vec![]));
+    assert_eq!(game.progress(), 1.0 / 11.0);
+  }
+
+  #[test]
+  fn first_click_policies_keep_their_area_free() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let start = BoardVec::new(5, 5);
+    for (policy, radius) in [
+      (FirstClickPolicy::CellOnly, 0),
+      (FirstClickPolicy::ThreeByThree, 1),
+      (FirstClickPolicy::GuaranteedFlood, 2),
+    ] {
+      for seed in 0..20 {
+        let mut builder = GameSetupBuilder::with_rng(11, 11, Box::new(StdRng::seed_from_u64(seed)));
+        builder.protect_start(start, policy);
+        assert!(builder.add_random_mines(60));
+        let game = Game::from(builder);
+        for pos in game.board().positions() {
+          let near = (pos.x - start.x).abs() <= radius && (pos.y - start.y).abs() <= radius;
+          assert!(!near || !game.board()[pos].is_mine(), "{:?} at {:?}", policy, pos);
+        }
+        if policy == FirstClickPolicy::GuaranteedFlood {
+          assert!(start.with_neighbours().all(|pos| game.board()[pos].is_blank()));
+        }
+      }
+
+      let game = no_guess_game((9, 9), 10, BoardVec::new(4, 4), policy, 3).unwrap();
+      assert!(policy
+        .safe_cells(BoardVec::new(4, 4))
+        .all(|pos| !game.board()[pos].is_mine()));
+    }
+  }
+
+  #[test]
+  fn field_char_conversions() {
+    assert_eq!(Field::try_from('3'), Ok(Field::Empty(3)));
+    assert_eq!(Field::try_from('X'), Ok(Field::Mine));
+    assert_eq!(Field::try_from('*'), Ok(Field::Mine));
+    assert_eq!(Field::try_from(' '), Ok(Field::Empty(0)));
+    assert_eq!(Field::try_from('.'), Ok(Field::Empty(0)));
+    assert_eq!(Field::try_from('q'), Err(InvalidFieldChar('q')));
+    assert_eq!(InvalidFieldChar('q').to_string(), "Invalid field character 'q'");
+
+    assert_eq!(Field::Mine.mine_count(), None);
+    assert_eq!(Field::Empty(4).mine_count(), Some(4));
+  }
+
+  /// `count` 9x9 setups with 10 mines, each with a free 3x3 area around `start`.
+  fn seeded_setups(count: u64, start: BoardVec) -> Vec<GameSetup> {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    (0..count)
+      .map(|seed| {
+        let mut builder = GameSetupBuilder::with_rng(9, 9, Box::new(StdRng::seed_from_u64(seed)));
+        builder.protect_start(start, FirstClickPolicy::ThreeByThree);
+        assert!(builder.add_random_mines(10));
+        GameSetup::from(builder)
+      })
+      .collect()
+  }
+
+  #[test]
+  #[cfg(feature = "parallel")]
+  fn count_solvable_matches_a_serial_count() {
+    let start = BoardVec::new(4, 4);
+    let setups = seeded_setups(40, start);
+    let serial = setups
+      .iter()
+      .filter(|setup| {
+        let mut game = Game::from((*setup).clone());
+        game.open(start);
+        game.is_solvable()
+      })
+      .count();
+    assert_eq!(count_solvable(&setups, start), serial);
+    assert!(serial > 0 && serial < 40, "{}", serial);
+  }
+
+  #[test]
+  fn restart_replays_the_same_layout() {
+    let rows = ["....", "....", "*..*"];
+    let mut game = Game::from(setup(&rows));
+    game.set_persistent_explorer(true);
+    let first = game.open(BoardVec::new(1, 0));
+    game.flag(BoardVec::new(0, 2));
+    game.open(BoardVec::new(0, 2));
+    assert!(game.is_lost());
+
+    game.restart();
+    let mut fresh = Game::from(setup(&rows));
+    fresh.set_persistent_explorer(true);
+    assert!(game == fresh);
+    assert_eq!(game.open(BoardVec::new(1, 0)), first);
+  }
+
+  #[test]
+  fn three_bv_counts_openings_and_isolated_numbers() {
+    // One opening covers the top-left 3x3 block; (3,1), (3,2), (1,3) and (2,3) must be clicked on their own.
+    assert_eq!(setup(&["...*", "....", "....", "*..*"]).three_bv(), 5);
+    assert_eq!(setup(&["...", "...", "..."]).three_bv(), 1);
+    assert_eq!(setup(&["*.*"]).three_bv(), 1);
+  }
+
+  #[test]
+  fn opening_an_open_number_chords_it() {
+    let mut game = Game::from(setup(&["*..", "...", "..."]));
+    assert_eq!(game.open(BoardVec::new(1, 0)), Some(vec![BoardVec::new(1, 0)]));
+    assert_eq!(game.open(BoardVec::new(1, 0)), Some(vec![]));
+
+    game.flag(BoardVec::new(0, 0));
+    let opened = game.open(BoardVec::new(1, 0)).unwrap();
+    assert!(opened.contains(&BoardVec::new(0, 1)) && opened.contains(&BoardVec::new(2, 2)));
+    assert!(game.is_win());
+  }
+
+  #[test]
+  fn chording_with_a_wrong_flag_hits_the_mine() {
+    let mut game = Game::from(setup(&["*..", "...", "..."]));
+    game.open(BoardVec::new(1, 0));
+    game.flag(BoardVec::new(0, 1));
+    assert_eq!(game.open(BoardVec::new(1, 0)), None);
+    assert!(game.is_lost());
+  }
+
+  #[test]
+  fn play_random_is_reproducible() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    assert!(Game::from(setup(&["."])).play_random(&mut StdRng::seed_from_u64(1)));
+
+    let play = |seed| {
+      let mut game = Game::from(setup(&["..*..", ".....", "*...*"]));
+      let won = game.play_random(&mut StdRng::seed_from_u64(seed));
+      (won, game)
+    };
+    for seed in 0..10 {
+      assert!(play(seed) == play(seed));
+    }
+  }
+
+  #[test]
+  fn render_char_shows_what_the_player_sees() {
+    let mut game = Game::from(setup(&["*..", "...", "..*"]));
+    assert_eq!(game.render_char(BoardVec::new(2, 0)), '░');
+    game.open(BoardVec::new(2, 0));
+    game.flag(BoardVec::new(0, 0));
+    assert_eq!(game.render_char(BoardVec::new(0, 0)), '⚑');
+    assert_eq!(game.render_char(BoardVec::new(1, 0)), '1');
+    assert_eq!(game.render_char(BoardVec::new(2, 0)), ' ');
+    assert_eq!(game.render_char(BoardVec::new(1, 1)), '2');
+  }
+
+  #[test]
+  fn count_neighbours_matches_the_numbers() {
+    let setup = setup(&["*.*", "...", ".**"]);
+    let board = &setup.board;
+    for pos in board.positions() {
+      if let Field::Empty(mines) = board[pos] {
+        assert_eq!(board.count_neighbours(pos, |field| field.is_mine()), mines);
+      }
+    }
+    assert_eq!(board.count_neighbours(BoardVec::new(1, 1), |field| field.is_mine()), 4);
+    assert_eq!(board.count_neighbours(BoardVec::new(0, 0), |field| !field.is_mine()), 3);
+  }
+
+  #[test]
+  fn opening_the_last_safe_cell_reports_the_win() {
+    let mut game = Game::from(setup(&["*.", ".."]));
+    assert!(matches!(
+      game.open_with_autoflag(BoardVec::new(1, 0)),
+      OpenOutcome::Opened { won: false, .. }
+    ));
+    game.open(BoardVec::new(0, 1));
+    assert!(matches!(
+      game.open_with_autoflag(BoardVec::new(1, 1)),
+      OpenOutcome::Opened { won: true, .. }
+    ));
+  }
+
+  #[test]
+  fn symmetries_of_mine_layouts() {
+    let diagonal = setup(&["*...", ".*..", "..*.", "...*"])
+      .symmetries()
+      .iter()
+      .collect::<Vec<_>>();
+    assert_eq!(
+      diagonal,
+      vec![Symmetry::Rotational180, Symmetry::Diagonal, Symmetry::AntiDiagonal]
+    );
+
+    let rotational = setup(&["**..", "...*", "*...", "..**"])
+      .symmetries()
+      .iter()
+      .collect::<Vec<_>>();
+    assert_eq!(rotational, vec![Symmetry::Rotational180]);
+
+    let column = setup(&["*..", "*.."]).symmetries().iter().collect::<Vec<_>>();
+    assert_eq!(column, vec![Symmetry::Vertical]);
+  }
+
+  #[test]
+  fn reveal_mines_after_a_loss() {
+    let mut game = Game::from(setup(&["*..", "..*", "*.."]));
+    game.flag(BoardVec::new(2, 1));
+    assert_eq!(game.open(BoardVec::new(0, 0)), None);
+
+    let revealed = game.reveal_mines();
+    assert_eq!(revealed.flagged, vec![BoardVec::new(2, 1)]);
+    assert_eq!(revealed.missed, vec![BoardVec::new(0, 0), BoardVec::new(0, 2)]);
+    assert!(revealed.missed.iter().all(|&pos| game.is_visible(pos)));
+    assert_eq!(game.render_char(BoardVec::new(0, 2)), 'X');
+  }
+
+  #[test]
+  #[should_panic(expected = "Mines can only be revealed after losing")]
+  fn reveal_mines_before_a_loss() {
+    Game::from(setup(&["*.."])).reveal_mines();
+  }
+
+  #[test]
+  fn filter_positions_agrees_with_a_scan() {
+    let setup = setup(&["*..", "...", "..*"]);
+    let board = &setup.board;
+    let blanks: Vec<BoardVec> = board.positions().filter(|&pos| board[pos].is_blank()).collect();
+    assert_eq!(board.filter_positions(|_, field| field.is_blank()), blanks);
+    assert_eq!(
+      board.filter_positions(|_, field| field.is_mine()),
+      vec![BoardVec::new(0, 0), BoardVec::new(2, 2)]
+    );
+    assert_eq!(board.filter_positions(|pos, _| pos.x == pos.y).len(), 3);
+  }
+
+  #[test]
+  fn to_builder_round_trip() {
+    let original = setup(&["*..", "..*", "*.."]);
+    assert!(GameSetup::from(original.to_builder()) == original);
+
+    let mut builder = original.to_builder();
+    assert!(builder.toggle_mine(BoardVec::new(1, 1)));
+    assert!(GameSetup::from(builder) == setup(&["*..", ".**", "*.."]));
+
+    let mut mines = Board::new(5, 5, false);
+    mines[BoardVec::new(1, 1)] = true;
+    let wide = GameSetup::with_radius(&mines, 2);
+    assert!(GameSetup::from(wide.to_builder()) == wide);
+  }
+
+  #[test]
+  fn compact_field_round_trip() {
+    for field in std::iter::once(Field::Mine).chain((0..=8).map(Field::Empty)) {
+      let compact = CompactField::try_from(field).unwrap();
+      assert_eq!(Field::from(compact), field);
+      assert_eq!(CompactField::try_from(compact.raw()), Ok(compact));
+    }
+    assert_eq!(std::mem::size_of::<CompactField>(), 1);
+  }
+
+  #[test]
+  fn invalid_compact_fields_are_rejected() {
+    assert_eq!(CompactField::try_from(0x81u8), Err(InvalidCompactField));
+    assert_eq!(CompactField::try_from(0xffu8), Err(InvalidCompactField));
+    assert_eq!(CompactField::try_from(Field::Empty(128)), Err(InvalidCompactField));
+    assert!(CompactField::try_from(Field::Empty(127)).is_ok());
+  }
+
+  #[test]
+  fn compact_board_keeps_the_numbers() {
+    let board = setup(&["..*", "*.."]).compact_board().unwrap();
+    assert_eq!(Field::from(board[BoardVec::new(1, 0)]), Field::Empty(2));
+    assert_eq!(Field::from(board[BoardVec::new(0, 1)]), Field::Mine);
+  }
+
+  #[test]
+  fn open_rings_group_by_flood_distance() {
+    let mut game = Game::from(setup(&[".....", ".....", ".....", "....*"]));
+    let rings = game.open_rings(BoardVec::new(1, 1)).unwrap();
+    assert_eq!(rings[0], vec![BoardVec::new(1, 1)]);
+    let mut first: Vec<BoardVec> = rings[1].clone();
+    first.sort_by_key(|pos| (pos.y, pos.x));
+    assert_eq!(first, BoardVec::new(1, 1).neighbours().collect::<Vec<_>>());
+    assert_eq!(rings.iter().map(Vec::len).sum::<usize>(), 19);
+    assert!(game.is_win());
+  }
+
+  #[test]
+  fn reopening_a_blank_field_opens_nothing() {
+    let mut game = Game::from(setup(&[".....", ".....", "*....", "....*"]));
+    let opened = game.open(BoardVec::new(4, 0)).unwrap();
+    assert!(!game.is_win());
+    let progress = game.progress();
+
+    assert_eq!(game.open(BoardVec::new(4, 0)), Some(vec![]));
+    assert_eq!(game.open_rings(opened[0]), Some(vec![]));
+    assert_eq!(game.progress(), progress);
+  }
+
+  #[test]
+  fn alternate_debug_is_annotated() {
+    let setup = setup(&["*..", "...", "..."]);
+    assert_eq!(format!("{:?}", setup), "X1 \n11 \n   \n");
+    assert_eq!(format!("{:#?}", setup), "3x3, 1 mines\n  012\n0 X1 \n1 11 \n2    \n");
+
+    let mut game = Game::from(setup);
+    game.open(BoardVec::new(2, 2));
+    assert_eq!(format!("{:#?}", game), "3x3, 1 mines\n  012\n0 ░1 \n1 11 \n2    \n");
+  }
+
+  #[test]
+  #[should_panic(expected = "Cannot create board with size 0x3: no fields")]
+  fn builder_without_fields() {
+    GameSetupBuilder::new(0, 3);
+  }
+
+  #[test]
+  fn limited_open_resumes_to_the_full_flood() {
+    let mut rows = vec![".".repeat(60); 40];
+    rows[39] = format!("{}*", ".".repeat(59));
+    let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+    let mut full = Game::from(setup(&rows));
+    let all = full.open(BoardVec::new(0, 0)).unwrap();
+
+    let mut game = Game::from(setup(&rows));
+    let first = game.open_limited(BoardVec::new(0, 0), Some(100)).unwrap();
+    assert_eq!(first.opened.len(), 100);
+    assert!(first.pending);
+    let mut opened = first.opened;
+    loop {
+      let next = game.resume_open(Some(333));
+      assert!(next.opened.len() <= 333);
+      opened.extend(next.opened);
+      if !next.pending {
+        break;
+      }
+    }
+    assert_eq!(opened.len(), all.len());
+    assert!(game.is_win());
+    assert_eq!(format!("{:?}", game), format!("{:?}", full));
+  }
+
+  #[test]
+  fn solvers_agree_lists_the_disagreements() {
+    let start = BoardVec::new(4, 4);
+    let setups = seeded_setups(30, start);
+    let same = |game: Game| game.is_solvable();
+    assert!(solvers_agree(&setups, start, &solver::Autoplay, &same).is_empty());
+
+    let never = |_: Game| false;
+    let solvable: Vec<usize> = (0..setups.len())
+      .filter(|&i| {
+        let mut game = Game::from(setups[i].clone());
+        game.open(start);
+        game.is_solvable()
+      })
+      .collect();
+    assert!(!solvable.is_empty());
+    assert_eq!(solvers_agree(&setups, start, &solver::Autoplay, &never), solvable);
+  }
+
+  #[test]
+  fn coordinate_shorthands_agree() {
+    let mut a = Game::from(setup(&["....", "*...", "...."]));
+    let mut b = a.clone();
+    assert_eq!(a.open_at(3, 0), b.open(BoardVec::new(3, 0)));
+    assert_eq!(a.flag_at(0, 1), b.flag(BoardVec::new(0, 1)));
+    for pos in a.board().positions() {
+      assert_eq!(a.view_at(pos.x, pos.y), b.view(pos));
+      assert_eq!(a.is_visible_at(pos.x, pos.y), b.is_visible(pos));
+    }
+    assert!(a == b);
+  }
+
+  #[test]
+  fn moves_outside_the_board_are_invalid() {
+    let game = Game::from(setup(&["....", "*...", "...."]));
+    assert!(!game.is_valid_move(&GameAction::Open(BoardVec::new(4, 0))));
+    assert!(!game.is_valid_move(&GameAction::Flag(BoardVec::new(-1, 0))));
+    assert!(!game.is_valid_move(&GameAction::Chord(BoardVec::new(0, 3))));
+    assert!(game.is_valid_move(&GameAction::Open(BoardVec::new(3, 0))));
+  }
+
+  #[test]
+  fn moves_must_fit_the_cell_state() {
+    let mut game = Game::from(setup(&["....", "*...", "...."]));
+    game.open(BoardVec::new(3, 0));
+    assert!(!game.is_valid_move(&GameAction::Open(BoardVec::new(3, 0))));
+    assert!(!game.is_valid_move(&GameAction::Chord(BoardVec::new(3, 0))));
+    assert!(game.is_valid_move(&GameAction::Chord(BoardVec::new(1, 1))));
+    assert!(game.is_valid_move(&GameAction::Flag(BoardVec::new(0, 1))));
+
+    game.flag(BoardVec::new(0, 1));
+    assert!(!game.is_valid_move(&GameAction::Flag(BoardVec::new(0, 1))));
+    assert!(!game.is_valid_move(&GameAction::Open(BoardVec::new(0, 1))));
+  }
+
+  #[test]
+  fn no_moves_after_a_loss() {
+    let mut game = Game::from(setup(&["....", "*...", "...."]));
+    game.open(BoardVec::new(0, 1));
+    assert!(!game.is_valid_move(&GameAction::Open(BoardVec::new(3, 0))));
+  }
+
+  #[test]
+  fn component_size_of_the_blank_flood() {
+    let mut game = Game::from(setup(&["*.....", "......", "....*.", "......"]));
+    let opened = game.open(BoardVec::new(5, 0)).unwrap().len();
+    let board = game.board();
+    assert_eq!(board.component_size(BoardVec::new(5, 0), |a, _| a.is_blank()), opened);
+    assert_eq!(
+      board.component_size(BoardVec::new(0, 0), |a, b| a.is_mine() == b.is_mine()),
+      1
+    );
+    assert_eq!(board.component_size(BoardVec::new(1, 1), |_, _| true), 24);
+    assert_eq!(board.component_size(BoardVec::new(9, 0), |_, _| true), 0);
+  }
+
+  #[test]
+  fn seeded_rng_makes_placement_deterministic() {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    let generate = || {
+      let mut builder = GameSetupBuilder::new(10, 10);
+      builder.set_rng(Box::new(StdRng::seed_from_u64(7)));
+      assert!(builder.add_random_mines(10));
+      GameSetup::from(builder)
+    };
+    assert!(generate() == generate());
+  }
+
+  #[test]
+  fn pre_opened_cells_start_open() {
+    let mut builder = GameSetupBuilder::new(6, 4);
+    builder.set_mine(BoardVec::new(5, 3));
+    builder.pre_open(BoardVec::new(0, 0));
+    builder.pre_open(BoardVec::new(5, 3));
+    builder.pre_open(BoardVec::new(6, 0));
+
+    let game = Game::from(&builder);
+    assert!(game.is_visible(BoardVec::new(0, 0)));
+    assert!(game.is_visible(BoardVec::new(3, 2)));
+    assert!(!game.is_visible(BoardVec::new(5, 3)));
+    assert!(!game.is_lost());
+    assert!(game.is_win());
+
+    let plain = Game::from(GameSetup::from(&builder));
+    assert!(!plain.is_visible(BoardVec::new(0, 0)));
+  }
+}
@@ -0,0 +1,244 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::board::{Board, BoardVec};
+use crate::{Game, GameBoard, GameSetup, ViewBoard, WinCondition};
+
+#[derive(Serialize)]
+struct BoardRef<'a, T: Serialize> {
+  width: u32,
+  height: u32,
+  origin: BoardVec,
+  fields: Fields<'a, T>,
+}
+
+struct Fields<'a, T>(&'a Board<T>);
+
+impl<T: Serialize> Serialize for Fields<'_, T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(self.0.iter())
+  }
+}
+
+#[derive(Deserialize)]
+struct BoardData<T> {
+  width: u32,
+  height: u32,
+  origin: BoardVec,
+  fields: Vec<T>,
+}
+
+impl<T: Serialize> Serialize for Board<T> {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    BoardRef {
+      width: self.width,
+      height: self.height,
+      origin: self.origin,
+      fields: Fields(self),
+    }
+    .serialize(serializer)
+  }
+}
+
+/// Rejects boards whose number of fields does not match their size.
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Board<T> {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let data = BoardData::deserialize(deserializer)?;
+    Board::from_flat(data.width, data.height, data.fields)
+      .map(|board| board.with_origin(data.origin))
+      .map_err(D::Error::custom)
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SetupData<B> {
+  board: B,
+  mines: u32,
+  total_mines: u32,
+  max_mines_per_cell: u32,
+  radius: i32,
+}
+
+impl Serialize for GameSetup {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    SetupData {
+      board: &self.board,
+      mines: self.mines,
+      total_mines: self.total_mines,
+      max_mines_per_cell: self.max_mines_per_cell,
+      radius: self.radius,
+    }
+    .serialize(serializer)
+  }
+}
+
+/// Rejects setups whose mine counts do not match the mines on the board.
+impl<'de> Deserialize<'de> for GameSetup {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let data = SetupData::<GameBoard>::deserialize(deserializer)?;
+    let mines = data.board.iter().filter(|field| field.is_mine()).count() as u32;
+    if mines != data.mines {
+      return Err(D::Error::custom(format!(
+        "Setup declares {} mines, but its board has {}",
+        data.mines, mines
+      )));
+    }
+    if data.radius < 1 {
+      return Err(D::Error::custom(format!(
+        "Radius must be at least 1, was {}",
+        data.radius
+      )));
+    }
+    let max_total = u64::from(mines) * u64::from(data.max_mines_per_cell);
+    if data.max_mines_per_cell == 0 || data.total_mines < mines || u64::from(data.total_mines) > max_total {
+      return Err(D::Error::custom(format!(
+        "Setup declares {} mines with at most {} per cell, but its board has {} cells with mines",
+        data.total_mines, data.max_mines_per_cell, mines
+      )));
+    }
+    Ok(GameSetup {
+      board: data.board,
+      mines: data.mines,
+      total_mines: data.total_mines,
+      max_mines_per_cell: data.max_mines_per_cell,
+      radius: data.radius,
+    })
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GameData<S, V> {
+  setup: S,
+  view: V,
+  flagged: V,
+  hidden_fields: u32,
+  win_condition: WinCondition,
+  lost: bool,
+}
+
+/// Saves the setup together with the progress. A persistent explorer and the observer are not saved.
+impl Serialize for Game {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    GameData {
+      setup: &self.setup,
+      view: &self.view,
+      flagged: &self.flagged,
+      hidden_fields: self.hidden_fields,
+      win_condition: self.win_condition,
+      lost: self.lost,
+    }
+    .serialize(serializer)
+  }
+}
+
+/// Rejects saves whose view does not fit the setup or disagrees with `hidden_fields`.
+impl<'de> Deserialize<'de> for Game {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+    let data = GameData::<GameSetup, ViewBoard>::deserialize(deserializer)?;
+    let setup = &data.setup;
+    for board in [&data.view, &data.flagged] {
+      if (board.width, board.height, board.origin) != (setup.width(), setup.height(), setup.board.origin) {
+        return Err(D::Error::custom("View does not match the size of the setup"));
+      }
+    }
+
+    // Mines only become visible through `reveal_mines`, which leaves `hidden_fields` alone.
+    let visible_mines = data
+      .view
+      .enumerate()
+      .filter(|&(pos, &visible)| visible && setup.board[pos].is_mine())
+      .count() as u32;
+    let hidden_fields = data.view.iter().filter(|&&visible| !visible).count() as u32 + visible_mines;
+    if hidden_fields != data.hidden_fields || hidden_fields < setup.mines {
+      return Err(D::Error::custom(format!(
+        "Save declares {} hidden fields, but its view has {}",
+        data.hidden_fields, hidden_fields
+      )));
+    }
+    if visible_mines > 0 && !data.lost {
+      return Err(D::Error::custom("View shows mines of a game that is not lost"));
+    }
+
+    let mut game = Game::from(data.setup);
+    game.view = data.view;
+    game.hidden_fields = hidden_fields;
+    game.win_condition = data.win_condition;
+    game.lost = data.lost;
+    for pos in data.flagged.positions().filter(|&pos| data.flagged[pos]) {
+      game.flagged[pos] = true;
+      *game.flag_counter(pos) += 1;
+    }
+    // A save taken during an `open_limited` flood has open blank cells with hidden, unflagged neighbours.
+    game.pending = game
+      .board()
+      .positions()
+      .filter(|&pos| game.view[pos] && game.board()[pos].is_blank())
+      .flat_map(|pos| game.board().neighbours_in_bounds(pos, game.setup.radius))
+      .filter(|&pos| !game.view[pos] && !game.flagged[pos])
+      .collect();
+    Ok(game)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::board::{Board, BoardVec};
+  use crate::{Game, GameSetup};
+
+  fn game(rows: &[&str]) -> Game {
+    Game::from(GameSetup::from_reader(rows.join("\n").as_bytes()).unwrap())
+  }
+
+  #[test]
+  fn game_round_trip() {
+    let mut game = game(&["*...", "....", "..*."]);
+    game.open(BoardVec::new(3, 0));
+    game.flag(BoardVec::new(0, 0));
+
+    let json = serde_json::to_string(&game).unwrap();
+    let restored: Game = serde_json::from_str(&json).unwrap();
+    assert_eq!(format!("{:?}", restored), format!("{:?}", game));
+    assert!(restored.is_flagged(BoardVec::new(0, 0)));
+    assert_eq!(restored.progress(), game.progress());
+  }
+
+  #[test]
+  fn tampered_hidden_fields_are_rejected() {
+    let mut game = game(&["*...", "....", "..*."]);
+    game.open(BoardVec::new(3, 0));
+    let mut json: serde_json::Value = serde_json::to_value(&game).unwrap();
+    json["hidden_fields"] = serde_json::Value::from(2);
+    let error = serde_json::from_value::<Game>(json).err().unwrap();
+    assert!(error.to_string().contains("hidden fields"));
+  }
+
+  #[test]
+  fn board_shape_is_checked() {
+    let board = Board::from_flat(2, 1, vec![1, 2])
+      .unwrap()
+      .with_origin(BoardVec::new(3, 4));
+    let json = serde_json::to_string(&board).unwrap();
+    assert!(serde_json::from_str::<Board<i32>>(&json).unwrap() == board);
+
+    let wrong = json.replace("[1,2]", "[1,2,3]");
+    assert!(serde_json::from_str::<Board<i32>>(&wrong).is_err());
+  }
+
+  #[test]
+  fn setup_with_wrong_mine_count_is_rejected() {
+    let mut json: serde_json::Value = serde_json::to_value(game(&["*.."]).setup()).unwrap();
+    json["mines"] = serde_json::Value::from(2);
+    let error = serde_json::from_value::<GameSetup>(json).err().unwrap();
+    assert_eq!(error.to_string(), "Setup declares 2 mines, but its board has 1");
+  }
+
+  #[test]
+  fn interrupted_flood_resumes_after_loading() {
+    let mut game = game(&["......", "......", "......", ".....*"]);
+    assert!(game.open_limited(BoardVec::new(0, 0), Some(3)).unwrap().pending);
+
+    let mut restored: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+    assert!(!restored.resume_open(None).pending);
+    assert!(restored.is_win());
+  }
+}
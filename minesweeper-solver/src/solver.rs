@@ -4,6 +4,31 @@ use std::collections::BinaryHeap;
 use crate::board::{Board, BoardExplorer, BoardVec};
 use crate::{Field, Game};
 
+pub mod dimacs;
+pub mod guess;
+pub mod probability;
+
+/// Decides whether a game can be won from its current view.
+pub trait Solver {
+  fn is_solvable(&self, game: Game) -> bool;
+}
+
+impl<F: Fn(Game) -> bool> Solver for F {
+  fn is_solvable(&self, game: Game) -> bool {
+    self(game)
+  }
+}
+
+/// The solver behind `Game::is_solvable`: propagation with deep suggestions, but no guessing.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Autoplay;
+
+impl Solver for Autoplay {
+  fn is_solvable(&self, game: Game) -> bool {
+    game.is_solvable()
+  }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct ExploredKnowlede {
   pub mines: u32,
@@ -12,12 +37,27 @@ pub struct ExploredKnowlede {
 }
 
 impl ExploredKnowlede {
+  pub fn is_satisfied(&self) -> bool {
+    self.mines_left == 0 && self.unknowns == 0
+  }
+
+  pub fn is_impossible(&self) -> bool {
+    self.mines_left > self.unknowns
+  }
+
   pub fn conclusion(&self) -> ExploredKnowledeConclusion {
+    self.conclusion_with_capacity(1)
+  }
+
+  /// Like `conclusion`, for variants where a cell holds up to `capacity` mines. `NeighboursAreMines` then
+  /// means that every unknown neighbour holds `mines_left / unknowns` mines.
+  pub fn conclusion_with_capacity(&self, capacity: u32) -> ExploredKnowledeConclusion {
     if self.unknowns > 0 {
-      if self.unknowns == self.mines_left {
-        NeighboursAreMines
-      } else if self.mines_left == 0 {
+      if self.mines_left == 0 {
         NeighboursAreNotMines
+      } else if self.unknowns * capacity == self.mines_left || (self.unknowns == 1 && self.mines_left <= capacity)
+      {
+        NeighboursAreMines
       } else {
         Unconclusive
       }
@@ -46,27 +86,189 @@ pub enum FieldKnowledge {
 
 use FieldKnowledge::*;
 
+impl fmt::Display for FieldKnowledge {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Unknown => write!(f, "░"),
+      Mine => write!(f, "X"),
+      NoMine => write!(f, "."),
+      Explored(explored) if explored.mines == 0 => write!(f, " "),
+      Explored(explored) => write!(f, "{}", explored.mines_left),
+    }
+  }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct State {
   board: Board<FieldKnowledge>,
+  /// Number of mines in each `Mine` field.
+  mine_counts: Board<u32>,
   mines_left: u32,
+  /// Probabilities and guesses assume a single mine per cell.
+  max_mines_per_cell: u32,
+  radius: i32,
 }
 
 impl State {
+  fn new(board: Board<FieldKnowledge>, mines: u32, max_mines_per_cell: u32, radius: i32) -> State {
+    State {
+      mine_counts: board.map(|_| 0),
+      board,
+      mines_left: mines,
+      max_mines_per_cell,
+      radius,
+    }
+  }
+
   pub fn knowledge_at(&self, pos: BoardVec) -> &FieldKnowledge {
     &self.board[pos]
   }
 
+  /// Bit `i` is set if the neighbour in direction `DIRECTIONS[i]` is unknown. Only looks at the 8 direct
+  /// neighbours, whatever the radius.
+  pub fn unknown_mask(&self, pos: BoardVec) -> u8 {
+    self.neighbour_mask(pos, Unknown)
+  }
+
+  /// Like `unknown_mask`, but for neighbours known to be mines.
+  pub fn mine_mask(&self, pos: BoardVec) -> u8 {
+    self.neighbour_mask(pos, Mine)
+  }
+
+  fn neighbour_mask(&self, pos: BoardVec, knowledge: FieldKnowledge) -> u8 {
+    pos
+      .neighbour_array()
+      .iter()
+      .enumerate()
+      .filter(|&(_, &n)| self.board.get(n) == Some(&knowledge))
+      .fold(0, |mask, (i, _)| mask | 1 << i)
+  }
+
+  /// Safe cells in row-major order.
   pub fn suggestions(&self) -> impl Iterator<Item = BoardVec> + '_ {
-    self.board.positions().filter(|&pos| self.board[pos] == NoMine)
+    let no_mines_left = self.mines_left == 0;
+    self
+      .board
+      .positions()
+      .filter(move |&pos| self.board[pos] == NoMine || (no_mines_left && self.board[pos] == Unknown))
+  }
+
+  pub fn known_mines(&self) -> impl Iterator<Item = BoardVec> + '_ {
+    self.board.positions().filter(|&pos| self.board[pos] == Mine)
   }
 
   pub fn into_mutator(self) -> StateMutator {
     StateMutator::new(self)
   }
 
+  /// Safe cells that follow from combining explored cells with each other and with the global mine count,
+  /// but not from any explored cell alone. In row-major order, excluding cells that are already `NoMine`.
+  pub fn deduced_safe(&self) -> Vec<BoardVec> {
+    let mut state = self.clone();
+    loop {
+      let (mines, safe) = state.eliminations();
+      if mines.is_empty() && safe.is_empty() {
+        break;
+      }
+      let mut mutator = state.into_mutator();
+      let marked = mines
+        .into_iter()
+        .try_for_each(|(pos, count)| mutator.mark_mines(pos, count))
+        .and_then(|_| safe.into_iter().try_for_each(|pos| mutator.mark_no_mine(pos)));
+      state = match marked.and_then(|_| mutator.finish_inner()) {
+        Ok(state) => state,
+        Err(_) => return Vec::new(),
+      };
+    }
+    self
+      .board
+      .positions()
+      .filter(|&pos| self.board[pos] == Unknown && state.board[pos] == NoMine)
+      .collect()
+  }
+
+  /// Cells that are mines (with their number of mines) or safe because the unknown neighbours of one explored
+  /// cell are a subset of another's, or because the frontier already needs all remaining mines.
+  fn eliminations(&self) -> (Vec<(BoardVec, u32)>, Vec<BoardVec>) {
+    let constraints: Vec<(BoardVec, Vec<BoardVec>, u32)> = self
+      .board
+      .enumerate()
+      .filter_map(|(pos, knowledge)| match knowledge {
+        Explored(explored) if explored.unknowns > 0 => {
+          let cells = self
+            .board
+            .around_in_radius(pos, self.radius)
+            .filter(|&(_, &k)| k == Unknown)
+            .map(|(n, _)| n)
+            .collect();
+          Some((pos, cells, explored.mines_left))
+        }
+        _ => None,
+      })
+      .collect();
+
+    let capacity = self.max_mines_per_cell;
+    let mut mines: Vec<(BoardVec, u32)> = Vec::new();
+    let mut safe: Vec<BoardVec> = Vec::new();
+    for (a_pos, a_cells, a_mines) in &constraints {
+      for (b_pos, b_cells, b_mines) in &constraints {
+        let dist = *b_pos - *a_pos;
+        if dist.x.abs() > 2 * self.radius || dist.y.abs() > 2 * self.radius || a_cells.len() >= b_cells.len() {
+          continue;
+        }
+        if !a_cells.iter().all(|cell| b_cells.contains(cell)) {
+          continue;
+        }
+        let rest = b_cells.iter().filter(|cell| !a_cells.contains(cell)).copied();
+        let rest_len = (b_cells.len() - a_cells.len()) as u32;
+        match b_mines.checked_sub(*a_mines) {
+          Some(0) => safe.extend(rest),
+          Some(m) if m == rest_len * capacity => mines.extend(rest.map(|cell| (cell, capacity))),
+          Some(m) if rest_len == 1 && m <= capacity => mines.extend(rest.map(|cell| (cell, m))),
+          _ => (),
+        }
+      }
+    }
+
+    // Constraints with disjoint cells need at least the sum of their mines.
+    let mut frontier = Board::new(self.board.width, self.board.height, false).with_origin(self.board.origin);
+    let mut required = 0;
+    for (_, cells, mines_left) in &constraints {
+      if cells.iter().all(|&cell| !frontier[cell]) {
+        required += mines_left;
+        for &cell in cells {
+          frontier[cell] = true;
+        }
+      }
+    }
+    for (_, cells, _) in &constraints {
+      for &cell in cells {
+        frontier[cell] = true;
+      }
+    }
+    if required == self.mines_left {
+      safe.extend(
+        self
+          .board
+          .positions()
+          .filter(|&pos| self.board[pos] == Unknown && !frontier[pos]),
+      );
+    }
+
+    mines.sort_by_key(|(pos, _)| (pos.y, pos.x));
+    mines.dedup();
+    safe.sort_by_key(|pos| (pos.y, pos.x));
+    safe.dedup();
+    (mines, safe)
+  }
+
+  /// Safe cells found by trying out mine placements, in row-major order. If cells can hold several mines,
+  /// only the combined deductions of `deduced_safe` are used.
   pub fn deep_suggestion(&self) -> Vec<BoardVec> {
-    debug_assert!(self.suggestions().next() == None);
+    debug_assert!(self.suggestions().next().is_none());
+    if self.max_mines_per_cell > 1 {
+      return self.deduced_safe();
+    }
     guess_run(self)
   }
 
@@ -77,7 +279,9 @@ impl State {
       if let Explored(explored) = board[pos] {
         if explored.unknowns > 0 && explored.mines > 0 {
           assert!(explored.mines_left > 0);
-          let impact = (8 - explored.unknowns) * 1000 / explored.mines_left;
+          // Cells at the border have fewer neighbours, so only count the ones on the board.
+          let neighbours = board.neighbour_count_in_radius(pos, self.radius);
+          let impact = (neighbours - explored.unknowns) * 1000 / explored.mines_left;
           result.push(GuessPos { impact, pos });
         }
       }
@@ -87,37 +291,131 @@ impl State {
   }
 }
 
-impl From<&Game> for State {
-  fn from(game: &Game) -> Self {
-    let mut mutator = StateMutator::new(State {
-      board: Board::new(game.width(), game.height(), Unknown),
-      mines_left: game.setup().mines,
-    });
-
-    for pos in game.board().positions() {
-      if let Some(field) = game.view(pos) {
-        mutator.mark_explored(pos, field);
+impl State {
+  /// The state of a hand-specified view, where `Some` marks an open field, with `mines` mines in total.
+  /// Uses the classic radius of 1.
+  pub fn from_view(view: &Board<Option<Field>>, mines: u32) -> State {
+    let board = Board::new(view.width, view.height, Unknown).with_origin(view.origin);
+    let open = view
+      .enumerate()
+      .filter_map(|(pos, field)| field.map(|field| (pos, field)));
+    Self::explore(State::new(board, mines, 1, 1), open).finish()
+  }
+
+  fn explore(state: State, open: impl Iterator<Item = (BoardVec, Field)>) -> StateMutator {
+    let mut mutator = StateMutator::new(state);
+
+    for (pos, field) in open {
+      mutator.mark_explored(pos, field);
+    }
+
+    mutator
+  }
+}
+
+impl State {
+  /// Like `State::from(game)`, but treats the player's flags as mines. Fails with the first flag (in row-major
+  /// order) that contradicts the open cells together with the flags before it. If cells can hold several
+  /// mines, a flag does not tell how many, so flags are ignored.
+  pub fn from_game_with_flags(game: &Game) -> Result<State, BoardVec> {
+    let mut mutator = State::from(game).into_mutator();
+    if mutator.state.max_mines_per_cell > 1 {
+      return Ok(mutator.state);
+    }
+    for flag in game.flags() {
+      match mutator.state.board[flag] {
+        Mine => continue,
+        NoMine | Explored(_) => return Err(flag),
+        Unknown => (),
+      }
+      mutator.mark_mines(flag, 1).map_err(|_| flag)?;
+      mutator.propagate().map_err(|_| flag)?;
+    }
+    Ok(mutator.state)
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObservationError {
+  /// The total number of mines cannot be placed consistently with the open cells.
+  MineTotal { expected: u32, found: (u32, u32) },
+  /// The open cell at this position contradicts the ones marked before it.
+  Inconsistent(BoardVec),
+}
+
+impl fmt::Display for ObservationError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ObservationError::MineTotal {
+        expected,
+        found: (min, max),
+      } => write!(
+        f,
+        "Expected {} mines in total, but the open cells allow between {} and {}",
+        expected, min, max
+      ),
+      ObservationError::Inconsistent(pos) => write!(f, "The open cell at {:?} contradicts the others", pos),
+    }
+  }
+}
+
+impl std::error::Error for ObservationError {}
+
+impl State {
+  /// Like `State::from_view`, but fails instead of panicking on contradicting numbers, and checks
+  /// `total_mines` against `mine_count_bounds`.
+  pub fn from_observations(view: &Board<Option<Field>>, total_mines: u32) -> Result<State, ObservationError> {
+    let explore = |mines: u32| {
+      let board = Board::new(view.width, view.height, Unknown).with_origin(view.origin);
+      let mut mutator = StateMutator::new(State::new(board, mines, 1, 1));
+      for (pos, field) in view.enumerate() {
+        if let Some(field) = *field {
+          mutator.try_mark_explored(pos, field)?;
+        }
       }
+      mutator.finish_inner()
+    };
+
+    // Without a limit on the total, only the numbers themselves can contradict each other.
+    let unlimited = explore(view.len() as u32).map_err(ObservationError::Inconsistent)?;
+    let (min, max) = unlimited.mine_count_bounds();
+    if total_mines < min || total_mines > max {
+      return Err(ObservationError::MineTotal {
+        expected: total_mines,
+        found: (min, max),
+      });
     }
+    explore(total_mines).map_err(ObservationError::Inconsistent)
+  }
+}
+
+impl From<&Game> for State {
+  fn from(game: &Game) -> Self {
+    StateMutator::from(game).finish()
+  }
+}
 
-    mutator.finish()
+/// The open cells of the game, with none of their conclusions drawn yet.
+impl From<&Game> for StateMutator {
+  fn from(game: &Game) -> Self {
+    let setup = game.setup();
+    let board = game.board().map(|_| Unknown);
+    let open = game
+      .board()
+      .positions()
+      .filter_map(|pos| game.view(pos).map(|field| (pos, field)));
+    let state = State::new(board, setup.total_mines(), setup.max_mines_per_cell(), setup.radius());
+    State::explore(state, open)
   }
 }
 
 impl fmt::Debug for State {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    for y in 0..self.board.height {
-      for x in 0..self.board.width {
-        let pos = BoardVec::new(x as i32, y as i32);
-        match self.board[pos] {
-          Unknown => write!(f, "░")?,
-          Mine => write!(f, "X")?,
-          NoMine => write!(f, ".")?,
-          Explored(explored) if explored.mines == 0 => write!(f, " ")?,
-          Explored(explored) => write!(f, "{}", explored.mines_left)?,
-        }
+    for (pos, knowledge) in self.board.enumerate() {
+      write!(f, "{}", knowledge)?;
+      if pos.x + 1 == self.board.origin.x + self.board.width as i32 {
+        writeln!(f)?;
       }
-      writeln!(f)?;
     }
 
     Ok(())
@@ -128,6 +426,10 @@ impl fmt::Debug for State {
 pub struct StateMutator {
   state: State,
   queue: BoardExplorer,
+  /// The explored cell whose neighbours are currently being concluded. It has no unknowns left
+  /// afterwards, so it must not be enqueued again.
+  resolving: Option<BoardVec>,
+  visits: usize,
 }
 
 impl StateMutator {
@@ -135,32 +437,49 @@ impl StateMutator {
     Self {
       queue: BoardExplorer::from(&state.board),
       state,
+      resolving: None,
+      visits: 0,
     }
   }
 
+  /// Number of explored cells taken from the propagation queue so far.
+  pub fn constraint_visits(&self) -> usize {
+    self.visits
+  }
+
   pub fn mark_explored(&mut self, pos: BoardVec, field: Field) {
+    self
+      .try_mark_explored(pos, field)
+      .unwrap_or_else(|pos| panic!("Number at {:?} contradicts the known mines", pos))
+  }
+
+  /// Like `mark_explored`, but returns `Err(pos)` if the number of the field is smaller than
+  /// the number of mines already known around it.
+  pub fn try_mark_explored(&mut self, pos: BoardVec, field: Field) -> Result<(), BoardVec> {
     match self.state.board[pos] {
       field_knowledge @ (Unknown | NoMine) => {
         if let Field::Empty(mines) = field {
+          // Cells at the border (or on tiny boards) have fewer neighbours than the full neighbourhood.
+          let neighbours: Vec<BoardVec> = self.state.board.neighbours_in_bounds(pos, self.state.radius).collect();
           let mut unknowns = 0;
           let mut mines_left = mines;
-          for neighbour_pos in pos.neighbours() {
+          for &neighbour_pos in &neighbours {
             match self.state.board.get_mut(neighbour_pos) {
               Some(Explored(explored)) => {
                 if field_knowledge == Unknown {
-                  debug_assert!(explored.unknowns > 0);
-                  explored.unknowns -= 1;
+                  explored.unknowns = explored.unknowns.checked_sub(1).ok_or(neighbour_pos)?;
                   let explored = *explored;
                   self.enqueue(neighbour_pos, explored);
                 }
               }
               Some(Mine) => {
-                debug_assert!(mines_left > 0);
-                mines_left -= 1;
+                mines_left = mines_left
+                  .checked_sub(self.state.mine_counts[neighbour_pos])
+                  .ok_or(pos)?;
               }
               Some(Unknown) => {
                 unknowns += 1;
-                debug_assert!(unknowns <= 8);
+                debug_assert!(unknowns as usize <= neighbours.len());
               }
               Some(NoMine) | None => (),
             }
@@ -179,30 +498,34 @@ impl StateMutator {
       Mine => panic!("Cannot mark a field with a mine as explored"),
       Explored(_) => panic!("Already marked as explored"),
     }
+    Ok(())
   }
 
-  fn mark_mine(&mut self, pos: BoardVec) -> Result<(), BoardVec> {
+  fn mark_mines(&mut self, pos: BoardVec, count: u32) -> Result<(), BoardVec> {
+    let capacity = self.state.max_mines_per_cell;
     match self.state.board[pos] {
       Unknown => {
-        if self.state.mines_left == 0 {
+        if self.state.mines_left < count {
           return Err(pos);
         }
-        self.state.mines_left -= 1;
+        self.state.mines_left -= count;
         self.state.board[pos] = Mine;
+        self.state.mine_counts[pos] = count;
 
-        for neighbour_pos in pos.neighbours() {
+        for neighbour_pos in pos.neighbours_in_radius(self.state.radius) {
           if let Some(Explored(explored)) = self.state.board.get_mut(neighbour_pos) {
-            if explored.mines_left == 0 || explored.unknowns < explored.mines_left {
+            if explored.mines_left < count || (explored.unknowns - 1) * capacity < explored.mines_left - count {
               return Err(pos);
             }
 
-            explored.mines_left -= 1;
+            explored.mines_left -= count;
             explored.unknowns -= 1;
             let explored = *explored;
             self.enqueue(neighbour_pos, explored);
           }
         }
       }
+      Mine if self.state.mine_counts[pos] != count => return Err(pos),
       Mine => (),
       Explored(_) | NoMine => panic!("We deduced that this field cannot be a mine."),
     }
@@ -211,13 +534,14 @@ impl StateMutator {
   }
 
   fn mark_no_mine(&mut self, pos: BoardVec) -> Result<(), BoardVec> {
+    let capacity = self.state.max_mines_per_cell;
     match self.state.board[pos] {
       Unknown => {
         self.state.board[pos] = NoMine;
-        for neighbour_pos in pos.neighbours() {
+        for neighbour_pos in pos.neighbours_in_radius(self.state.radius) {
           if let Some(Explored(explored)) = self.state.board.get_mut(neighbour_pos) {
             debug_assert!(explored.unknowns > 0);
-            if explored.unknowns <= explored.mines_left {
+            if (explored.unknowns - 1) * capacity < explored.mines_left {
               return Err(pos);
             }
             explored.unknowns -= 1;
@@ -233,7 +557,8 @@ impl StateMutator {
   }
 
   fn enqueue(&mut self, pos: BoardVec, explored: ExploredKnowlede) {
-    if explored.conclusion() != Unconclusive {
+    let conclusion = explored.conclusion_with_capacity(self.state.max_mines_per_cell);
+    if conclusion != Unconclusive && self.resolving != Some(pos) {
       self.queue.enqueue(pos);
     }
   }
@@ -242,33 +567,136 @@ impl StateMutator {
   }
 
   fn finish_inner(mut self) -> Result<State, BoardVec> {
+    self.propagate()?;
+    Ok(self.state)
+  }
+
+  /// Draws all conclusions from the explored cells marked so far.
+  pub fn propagate(&mut self) -> Result<(), BoardVec> {
+    while let Some(pos) = self.next_queued() {
+      self.resolve(pos)?;
+    }
+
+    Ok(())
+  }
+
+  /// Resolves queued explored cells until one of them concludes a safe cell and returns that cell, leaving
+  /// the rest pending. Once nothing is left to propagate, falls back to the state's first suggestion.
+  /// Returns `None` on a contradiction.
+  pub fn first_safe(&mut self) -> Option<BoardVec> {
+    while let Some(concluded) = self.step().ok()? {
+      if let Some(&pos) = concluded.iter().find(|&&pos| self.state.board[pos] == NoMine) {
+        return Some(pos);
+      }
+    }
+    self.state.suggestions().next()
+  }
+
+  /// Propagates lazily, yielding every concluded cell as soon as it is found.
+  pub fn deductions(self) -> Deductions {
+    Deductions {
+      mutator: self,
+      pending: Vec::new().into_iter(),
+    }
+  }
+
+  /// Resolves a single explored cell and returns the cells concluded by it,
+  /// or `None` if there is nothing left to propagate.
+  pub fn step(&mut self) -> Result<Option<Vec<BoardVec>>, BoardVec> {
+    let pos = match self.next_queued() {
+      Some(pos) => pos,
+      None => return Ok(None),
+    };
+    let unknowns: Vec<BoardVec> = self
+      .state
+      .board
+      .around_in_radius(pos, self.state.radius)
+      .filter(|&(_, &k)| k == Unknown)
+      .map(|(n, _)| n)
+      .collect();
+    self.resolve(pos)?;
+    Ok(Some(
+      unknowns
+        .into_iter()
+        .filter(|&n| self.state.board[n] != Unknown)
+        .collect(),
+    ))
+  }
+
+  fn next_queued(&mut self) -> Option<BoardVec> {
     self.queue.set_allow_multiple_enqueue(true);
-    while let Some(pos) = self.queue.pop() {
-      let explored = if let Explored(explored) = &self.state.board[pos] {
-        explored
-      } else {
-        panic!("Only explored fields can be of interest.")
-      };
-      match explored.conclusion() {
-        NeighboursAreNotMines => {
-          for neighbour_pos in pos.neighbours() {
-            if let Some(Unknown) = self.state.board.get(neighbour_pos) {
-              self.mark_no_mine(neighbour_pos)?;
-            }
+    let pos = self.queue.pop()?;
+    self.visits += 1;
+    Some(pos)
+  }
+
+  fn resolve(&mut self, pos: BoardVec) -> Result<(), BoardVec> {
+    let explored = match self.state.board[pos] {
+      Explored(explored) => explored,
+      _ => panic!("Only explored fields can be of interest."),
+    };
+
+    self.resolving = Some(pos);
+    let result = self.resolve_neighbours(pos, explored);
+    self.resolving = None;
+    result
+  }
+
+  fn resolve_neighbours(&mut self, pos: BoardVec, explored: ExploredKnowlede) -> Result<(), BoardVec> {
+    if explored.mines_left > explored.unknowns * self.state.max_mines_per_cell {
+      return Err(pos);
+    }
+    match explored.conclusion_with_capacity(self.state.max_mines_per_cell) {
+      NeighboursAreNotMines => {
+        for neighbour_pos in pos.neighbours_in_radius(self.state.radius) {
+          if let Some(Unknown) = self.state.board.get(neighbour_pos) {
+            self.mark_no_mine(neighbour_pos)?;
           }
         }
-        NeighboursAreMines => {
-          for neighbour_pos in pos.neighbours() {
-            if let Some(Unknown) = self.state.board.get(neighbour_pos) {
-              self.mark_mine(neighbour_pos)?;
-            }
+      }
+      NeighboursAreMines => {
+        let count = explored.mines_left / explored.unknowns;
+        for neighbour_pos in pos.neighbours_in_radius(self.state.radius) {
+          if let Some(Unknown) = self.state.board.get(neighbour_pos) {
+            self.mark_mines(neighbour_pos, count)?;
           }
         }
-        _ => (),
       }
+      Unconclusive => (),
     }
 
-    Ok(self.state)
+    Ok(())
+  }
+}
+
+/// Iterator returned by `StateMutator::deductions`. Each `next` resolves explored cells until one concludes
+/// something.
+pub struct Deductions {
+  mutator: StateMutator,
+  pending: std::vec::IntoIter<BoardVec>,
+}
+
+impl Deductions {
+  /// The state with everything yielded so far. Propagates the rest if the iterator is not exhausted yet.
+  pub fn into_state(self) -> State {
+    self.mutator.finish()
+  }
+}
+
+impl Iterator for Deductions {
+  type Item = (BoardVec, FieldKnowledge);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    loop {
+      if let Some(pos) = self.pending.next() {
+        return Some((pos, self.mutator.state.board[pos]));
+      }
+      let concluded = self
+        .mutator
+        .step()
+        .unwrap_or_else(|pos| panic!("Contradiction at {:?}", pos))?;
+      self.pending = concluded.into_iter();
+    }
   }
 }
 
@@ -301,10 +729,10 @@ fn guess_run(state: &State) -> Vec<BoardVec> {
     //println!("===== {:?} ====", pos);
     let mut succeeded = None;
     let mut result = Vec::new();
-    for neighbour_pos in pos.neighbours() {
+    for neighbour_pos in pos.neighbours_in_radius(state.radius) {
       if let Some(Unknown) = state.board.get(neighbour_pos) {
         let mut mutator = state.clone().into_mutator();
-        mutator.mark_mine(neighbour_pos).unwrap();
+        mutator.mark_mines(neighbour_pos, 1).unwrap();
         match (mutator.finish_inner(), &succeeded) {
           (Ok(state), Some(succeeded)) if &state != succeeded => {
             //println!("tried:\n{:?}\nHad:\n{:?}", succeeded, state);
@@ -318,7 +746,7 @@ fn guess_run(state: &State) -> Vec<BoardVec> {
 
     if let Some(state) = succeeded {
       result.extend(state.suggestions());
-      result.sort_by(|a, b| a.x.cmp(&b.x).then(a.y.cmp(&a.y)));
+      result.sort_by(|a, b| a.y.cmp(&b.y).then(a.x.cmp(&b.x)));
       result.dedup();
       return result;
     }
@@ -326,3 +754,356 @@ fn guess_run(state: &State) -> Vec<BoardVec> {
 
   Vec::new()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::board::BoardVec;
+  use crate::GameSetup;
+
+  fn setup(rows: &[&str]) -> GameSetup {
+    GameSetup::from_reader(rows.join("\n").as_bytes()).unwrap()
+  }
+
+  #[test]
+  fn single_unknown_holds_all_mines_left() {
+    let explored = ExploredKnowlede {
+      mines: 2,
+      mines_left: 2,
+      unknowns: 1,
+    };
+    assert_eq!(explored.conclusion_with_capacity(2), NeighboursAreMines);
+    assert_eq!(explored.conclusion_with_capacity(1), Unconclusive);
+
+    let two_unknowns = ExploredKnowlede {
+      unknowns: 2,
+      ..explored
+    };
+    assert_eq!(two_unknowns.conclusion_with_capacity(2), Unconclusive);
+    assert_eq!(two_unknowns.conclusion_with_capacity(1), NeighboursAreMines);
+  }
+
+  #[test]
+  fn multi_mine_cells_are_counted_in_neighbours() {
+    // A cell with two mines in the corner of a 2x2 board, all other cells open.
+    let mut counts = Board::new(2, 2, 0);
+    counts[BoardVec::new(0, 0)] = 2;
+    let mut game = Game::from(GameSetup::from_mine_counts(&counts, 2));
+    game.open(BoardVec::new(1, 0));
+    game.open(BoardVec::new(0, 1));
+    game.open(BoardVec::new(1, 1));
+
+    let state = State::from(&game);
+    assert_eq!(state.known_mines().collect::<Vec<_>>(), vec![BoardVec::new(0, 0)]);
+    assert_eq!(state.mine_counts[BoardVec::new(0, 0)], 2);
+    assert_eq!(state.mine_count_bounds(), (2, 2));
+  }
+
+  #[test]
+  fn debug_of_origin_state() {
+    let view = Board::from_flat(3, 1, vec![Some(Field::Empty(0)), Some(Field::Empty(1)), None])
+      .unwrap()
+      .with_origin(BoardVec::new(5, 5));
+    let state = State::from_view(&view, 1);
+    assert_eq!(state.known_mines().collect::<Vec<_>>(), vec![BoardVec::new(7, 5)]);
+    assert_eq!(format!("{:?}", state), " 0X\n");
+  }
+
+  #[test]
+  fn guess_positions_count_on_board_neighbours() {
+    let mut game = Game::from(setup(&["...**", "...*.", ".....", "....*"]));
+    game.open(BoardVec::new(0, 0));
+    let state = State::from(&game);
+    assert_eq!(
+      state.find_guess_positions().peek().map(|guess| guess.pos),
+      Some(BoardVec::new(3, 2))
+    );
+
+    // Assuming 8 neighbours everywhere ranked the 1 at the bottom edge first.
+    let fixed_neighbourhood = state
+      .board
+      .enumerate()
+      .filter_map(|(pos, knowledge)| match knowledge {
+        Explored(explored) if explored.unknowns > 0 && explored.mines > 0 => Some(GuessPos {
+          impact: (8 - explored.unknowns) * 1000 / explored.mines_left,
+          pos,
+        }),
+        _ => None,
+      })
+      .max();
+    assert_eq!(fixed_neighbourhood.map(|guess| guess.pos), Some(BoardVec::new(3, 3)));
+  }
+
+  #[test]
+  fn from_observations_checks_the_mine_total() {
+    let mut view = Board::new(3, 1, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    assert!(State::from_observations(&view, 1).is_ok());
+    assert!(State::from_observations(&view, 2).is_ok());
+    assert_eq!(
+      State::from_observations(&view, 0).err(),
+      Some(ObservationError::MineTotal {
+        expected: 0,
+        found: (1, 2)
+      })
+    );
+    assert_eq!(
+      State::from_observations(&view, 3).err(),
+      Some(ObservationError::MineTotal {
+        expected: 3,
+        found: (1, 2)
+      })
+    );
+  }
+
+  #[test]
+  fn from_observations_reports_contradictions() {
+    let mut view = Board::new(3, 1, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    view[BoardVec::new(1, 0)] = Some(Field::Empty(3));
+    // Opening (1, 0) leaves no room for the mine next to (0, 0).
+    assert_eq!(
+      State::from_observations(&view, 1).err(),
+      Some(ObservationError::Inconsistent(BoardVec::new(0, 0)))
+    );
+    assert!(State::from_observations(&view, 3).is_err());
+  }
+
+  #[test]
+  fn field_knowledge_display() {
+    let explored = |mines, mines_left| {
+      crate::FieldKnowledge::Explored(crate::ExploredKnowlede {
+        mines,
+        mines_left,
+        unknowns: 2,
+      })
+    };
+    assert_eq!(Unknown.to_string(), "░");
+    assert_eq!(Mine.to_string(), "X");
+    assert_eq!(NoMine.to_string(), ".");
+    assert_eq!(explored(0, 0).to_string(), " ");
+    assert_eq!(explored(3, 1).to_string(), "1");
+  }
+
+  #[test]
+  fn no_mines_left_suggests_every_unknown() {
+    let mut view = Board::new(5, 1, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    let state = State::from_view(&view, 1);
+    assert_eq!(
+      state.suggestions().collect::<Vec<_>>(),
+      vec![BoardVec::new(2, 0), BoardVec::new(3, 0), BoardVec::new(4, 0)]
+    );
+    assert_eq!(State::from_view(&view, 2).suggestions().next(), None);
+  }
+
+  #[test]
+  fn suggestions_are_in_row_major_order() {
+    let mut game = Game::from(setup(&[".....", "...*.", "...*.", ".*.**"]));
+    game.open(BoardVec::new(0, 0));
+    let state = State::from(&game);
+    assert_eq!(state.suggestions().next(), None);
+    assert_eq!(
+      state.deep_suggestion(),
+      vec![BoardVec::new(3, 0), BoardVec::new(0, 3), BoardVec::new(2, 3)]
+    );
+
+    let mut view = Board::new(3, 2, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(0));
+    let state = State::from_view(&view, 0);
+    assert_eq!(
+      state.suggestions().collect::<Vec<_>>(),
+      vec![
+        BoardVec::new(1, 0),
+        BoardVec::new(2, 0),
+        BoardVec::new(0, 1),
+        BoardVec::new(1, 1),
+        BoardVec::new(2, 1)
+      ]
+    );
+  }
+
+  #[test]
+  fn satisfied_and_impossible_knowledge() {
+    let knowledge = |mines_left, unknowns| ExploredKnowlede {
+      mines: 2,
+      mines_left,
+      unknowns,
+    };
+    assert!(knowledge(0, 0).is_satisfied());
+    assert!(!knowledge(0, 0).is_impossible());
+
+    assert!(!knowledge(1, 2).is_satisfied());
+    assert!(!knowledge(1, 2).is_impossible());
+    assert!(!knowledge(0, 1).is_satisfied());
+
+    assert!(knowledge(2, 1).is_impossible());
+    assert!(knowledge(1, 0).is_impossible());
+  }
+
+  #[test]
+  fn resolved_cells_are_visited_once() {
+    let mut mutator = State::from(&Game::from(setup(&["*.*"]))).into_mutator();
+    mutator.mark_explored(BoardVec::new(1, 0), Field::Empty(2));
+    mutator.propagate().unwrap();
+    assert_eq!(mutator.constraint_visits(), 1);
+
+    let mut mutator = State::from(&Game::from(setup(&["*.*", "*.*"]))).into_mutator();
+    mutator.mark_explored(BoardVec::new(1, 0), Field::Empty(4));
+    mutator.mark_explored(BoardVec::new(1, 1), Field::Empty(4));
+    mutator.propagate().unwrap();
+    assert_eq!(mutator.constraint_visits(), 2);
+    let state = mutator.finish();
+    assert!([(0, 0), (2, 0), (0, 1), (2, 1)]
+      .iter()
+      .all(|&(x, y)| *state.knowledge_at(BoardVec::new(x, y)) == Mine));
+  }
+
+  #[test]
+  fn number_below_known_mines_is_an_error() {
+    let mut mutator = State::from(&Game::from(setup(&[".*.", "..."]))).into_mutator();
+    mutator.mark_explored(BoardVec::new(2, 0), Field::Empty(1));
+    mutator.mark_explored(BoardVec::new(1, 1), Field::Empty(1));
+    mutator.mark_explored(BoardVec::new(2, 1), Field::Empty(1));
+    let state = mutator.finish();
+    assert_eq!(*state.knowledge_at(BoardVec::new(1, 0)), Mine);
+
+    let mut mutator = state.into_mutator();
+    assert_eq!(
+      mutator.try_mark_explored(BoardVec::new(0, 0), Field::Empty(0)),
+      Err(BoardVec::new(0, 0))
+    );
+  }
+
+  #[test]
+  fn first_safe_stops_at_the_first_safe_cell() {
+    let rows: Vec<String> = (0..60)
+      .map(|y| {
+        (0..80)
+          .map(|x| if (x * 7 + y * 13) % 11 == 5 { '*' } else { '.' })
+          .collect()
+      })
+      .collect();
+    let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+    let mut game = Game::from(setup(&rows));
+    // Scattered single cells, each with deductions of its own. The blank (0, 0) comes first.
+    let scattered: Vec<BoardVec> = game
+      .board()
+      .positions()
+      .filter(|&pos| pos.x % 3 == 0 && pos.y % 3 == 0 && !game.board()[pos].is_mine())
+      .collect();
+    for &pos in &scattered {
+      game.force_open(pos, false);
+    }
+
+    let mut mutator = StateMutator::from(&game);
+    let safe = mutator.first_safe().unwrap();
+    assert!(!game.board()[safe].is_mine() && !game.is_visible(safe));
+    assert!(State::from(&game).suggestions().any(|pos| pos == safe));
+    assert_eq!(mutator.constraint_visits(), 1);
+
+    let mut full = StateMutator::from(&game);
+    full.propagate().unwrap();
+    assert!(full.constraint_visits() > 100);
+  }
+
+  #[test]
+  fn first_safe_without_deductions() {
+    let mut game = Game::from(setup(&["*.", ".*"]));
+    game.open(BoardVec::new(1, 0));
+    assert_eq!(StateMutator::from(&game).first_safe(), None);
+  }
+
+  #[test]
+  fn tiny_boards() {
+    for rows in [&["."][..], &[".."], &[".", "."], &["*"], &[".*"], &["*", "."]] {
+      let mut game = Game::from(setup(rows));
+      State::from(&game).into_mutator().finish();
+      if let Some(safe) = game.board().positions().find(|&pos| !game.board()[pos].is_mine()) {
+        game.open(safe);
+        let state = State::from(&game);
+        for pos in game.board().positions().filter(|&pos| game.board()[pos].is_mine()) {
+          assert_eq!(*state.knowledge_at(pos), Mine, "{:?}", rows);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn deduced_safe_from_a_one_one_pattern() {
+    let mut game = Game::from(setup(&["....", "*...", "...."]));
+    game.force_open(BoardVec::new(0, 0), false);
+    game.force_open(BoardVec::new(1, 0), false);
+    let state = State::from(&game);
+    assert_eq!(state.suggestions().count(), 0);
+
+    let safe = state.deduced_safe();
+    assert!(safe.contains(&BoardVec::new(2, 0)) && safe.contains(&BoardVec::new(2, 1)));
+    assert!(safe.iter().all(|&pos| !game.board()[pos].is_mine()));
+    assert_eq!(game.tipp(), safe);
+  }
+
+  #[test]
+  fn deduced_safe_from_the_mine_count() {
+    let mut game = Game::from(setup(&["*.....", "......", "......"]));
+    game.force_open(BoardVec::new(1, 0), false);
+    let safe = State::from(&game).deduced_safe();
+    assert!(safe.contains(&BoardVec::new(5, 2)));
+    assert!(safe.iter().all(|&pos| !game.board()[pos].is_mine()));
+  }
+
+  #[test]
+  fn deductions_match_the_finished_state() {
+    let mut game = Game::from(setup(&["......", "*.....", ".....*", "......", "*....."]));
+    game.open(BoardVec::new(3, 0));
+    let state = State::from(&game);
+
+    let mut deductions = StateMutator::from(&game).deductions();
+    let deduced: Vec<(BoardVec, FieldKnowledge)> = deductions.by_ref().collect();
+    assert!(!deduced.is_empty());
+    for (pos, knowledge) in &deduced {
+      assert!(matches!(knowledge, Mine | NoMine));
+      assert_eq!(state.knowledge_at(*pos), knowledge);
+    }
+    let concluded = game
+      .board()
+      .positions()
+      .filter(|&pos| matches!(state.knowledge_at(pos), Mine | NoMine));
+    assert_eq!(deduced.len(), concluded.count());
+    assert!(deductions.into_state() == state);
+  }
+
+  #[test]
+  fn correct_flag_enables_a_deduction() {
+    let mut game = Game::from(setup(&["...", "*..", "..."]));
+    game.force_open(BoardVec::new(0, 0), false);
+    assert_eq!(State::from(&game).suggestions().count(), 0);
+
+    game.flag(BoardVec::new(0, 1));
+    let state = State::from_game_with_flags(&game).unwrap();
+    assert!(state.suggestions().any(|pos| pos == BoardVec::new(1, 1)));
+  }
+
+  #[test]
+  fn contradicting_flag_is_reported() {
+    let mut game = Game::from(setup(&["...", "*..", "..."]));
+    game.force_open(BoardVec::new(0, 0), false);
+    game.flag(BoardVec::new(1, 0));
+    game.flag(BoardVec::new(1, 1));
+    assert_eq!(State::from_game_with_flags(&game), Err(BoardVec::new(1, 1)));
+  }
+
+  #[test]
+  fn neighbour_masks_follow_the_directions() {
+    let mut game = Game::from(setup(&["...", "*..", "..."]));
+    game.force_open(BoardVec::new(0, 0), false);
+    game.flag(BoardVec::new(0, 1));
+    let state = State::from_game_with_flags(&game).unwrap();
+    let bit = |direction: BoardVec| 1 << crate::board::DIRECTIONS.iter().position(|&d| d == direction).unwrap();
+
+    assert_eq!(state.unknown_mask(BoardVec::new(0, 0)), 0);
+    assert_eq!(state.mine_mask(BoardVec::new(0, 0)), bit(BoardVec::new(0, 1)));
+    assert_eq!(state.mine_mask(BoardVec::new(1, 1)), bit(BoardVec::new(-1, 0)));
+    assert_eq!(state.unknown_mask(BoardVec::new(1, 1)), 0b1111_0100);
+  }
+}
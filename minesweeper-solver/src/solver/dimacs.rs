@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::FieldKnowledge::*;
+use super::State;
+use crate::board::BoardVec;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Dimacs {
+  pub cnf: String,
+  /// `variables[i]` is the cell encoded by variable `i + 1`. Higher variables are auxiliary.
+  pub variables: Vec<BoardVec>,
+}
+
+impl State {
+  /// Encodes the unknown frontier cells as variables (true = mine), with one cardinality constraint per
+  /// explored cell and one for the global mine count.
+  pub fn to_dimacs(&self) -> Dimacs {
+    let variables: Vec<BoardVec> = self
+      .frontier_regions()
+      .into_iter()
+      .flat_map(|region| region.cells)
+      .collect();
+    let literals: HashMap<BoardVec, i32> = variables
+      .iter()
+      .enumerate()
+      .map(|(i, &pos)| (pos, i as i32 + 1))
+      .collect();
+
+    let mut cnf = Cnf {
+      variables: variables.len() as i32,
+      clauses: Vec::new(),
+    };
+
+    for (pos, knowledge) in self.board.enumerate() {
+      if let Explored(explored) = knowledge {
+        let neighbours: Vec<i32> = pos
+          .neighbours_in_radius(self.radius)
+          .filter_map(|n| literals.get(&n).copied())
+          .collect();
+        if !neighbours.is_empty() {
+          cnf.exactly(&neighbours, explored.mines_left as usize);
+        }
+      }
+    }
+
+    let all: Vec<i32> = (1..=variables.len() as i32).collect();
+    let unknowns = self.board.iter().filter(|&&k| k == Unknown).count();
+    let interior = unknowns - variables.len();
+    let mines = self.mines_left as usize;
+    cnf.at_most(&all, mines);
+    cnf.at_least(&all, mines.saturating_sub(interior));
+
+    let mut text = String::new();
+    for (i, pos) in variables.iter().enumerate() {
+      writeln!(text, "c {} = {:?}", i + 1, pos).unwrap();
+    }
+    writeln!(text, "p cnf {} {}", cnf.variables, cnf.clauses.len()).unwrap();
+    for clause in &cnf.clauses {
+      for literal in clause {
+        write!(text, "{} ", literal).unwrap();
+      }
+      writeln!(text, "0").unwrap();
+    }
+
+    Dimacs { cnf: text, variables }
+  }
+}
+
+struct Cnf {
+  variables: i32,
+  clauses: Vec<Vec<i32>>,
+}
+
+impl Cnf {
+  fn new_variable(&mut self) -> i32 {
+    self.variables += 1;
+    self.variables
+  }
+
+  fn exactly(&mut self, literals: &[i32], k: usize) {
+    self.at_most(literals, k);
+    self.at_least(literals, k);
+  }
+
+  fn at_least(&mut self, literals: &[i32], k: usize) {
+    if k > literals.len() {
+      let contradiction = self.new_variable();
+      self.clauses.push(vec![contradiction]);
+      self.clauses.push(vec![-contradiction]);
+      return;
+    }
+    let negated: Vec<i32> = literals.iter().map(|l| -l).collect();
+    self.at_most(&negated, literals.len() - k);
+  }
+
+  /// Sequential counter encoding: `counters[i][j]` holds if more than `j` of the first `i + 1` literals are true.
+  fn at_most(&mut self, literals: &[i32], k: usize) {
+    let n = literals.len();
+    if k >= n {
+      return;
+    }
+    if k == 0 {
+      for &literal in literals {
+        self.clauses.push(vec![-literal]);
+      }
+      return;
+    }
+
+    let counters: Vec<Vec<i32>> = (0..n - 1)
+      .map(|_| (0..k).map(|_| self.new_variable()).collect())
+      .collect();
+
+    self.clauses.push(vec![-literals[0], counters[0][0]]);
+    for &counter in &counters[0][1..] {
+      self.clauses.push(vec![-counter]);
+    }
+
+    for i in 1..n - 1 {
+      self.clauses.push(vec![-literals[i], counters[i][0]]);
+      self.clauses.push(vec![-counters[i - 1][0], counters[i][0]]);
+      for j in 1..k {
+        self
+          .clauses
+          .push(vec![-literals[i], -counters[i - 1][j - 1], counters[i][j]]);
+        self.clauses.push(vec![-counters[i - 1][j], counters[i][j]]);
+      }
+      self.clauses.push(vec![-literals[i], -counters[i - 1][k - 1]]);
+    }
+
+    self.clauses.push(vec![-literals[n - 1], -counters[n - 2][k - 1]]);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Game, GameSetup};
+
+  fn dimacs_of(rows: &[&str], start: BoardVec) -> Dimacs {
+    let mut game = Game::from(GameSetup::from_reader(rows.join("\n").as_bytes()).unwrap());
+    game.open(start);
+    State::from(&game).to_dimacs()
+  }
+
+  /// Parses the clauses of `cnf`, adds `assumptions` as unit clauses and checks satisfiability.
+  fn satisfiable(cnf: &str, assumptions: &[i32]) -> bool {
+    let mut clauses: Vec<Vec<i32>> = cnf
+      .lines()
+      .filter(|line| !line.starts_with('c') && !line.starts_with('p'))
+      .map(|line| {
+        line
+          .split_whitespace()
+          .map(|literal| literal.parse().unwrap())
+          .filter(|&literal| literal != 0)
+          .collect()
+      })
+      .collect();
+    clauses.extend(assumptions.iter().map(|&literal| vec![literal]));
+    dpll(clauses)
+  }
+
+  fn dpll(clauses: Vec<Vec<i32>>) -> bool {
+    if clauses.is_empty() {
+      return true;
+    }
+    if clauses.iter().any(|clause| clause.is_empty()) {
+      return false;
+    }
+    let literal = clauses
+      .iter()
+      .find(|clause| clause.len() == 1)
+      .map_or(clauses[0][0], |clause| clause[0]);
+    let assign = |literal: i32| -> Vec<Vec<i32>> {
+      clauses
+        .iter()
+        .filter(|clause| !clause.contains(&literal))
+        .map(|clause| clause.iter().copied().filter(|&l| l != -literal).collect())
+        .collect()
+    };
+    dpll(assign(literal)) || dpll(assign(-literal))
+  }
+
+  #[test]
+  fn forced_cells_cannot_be_flipped() {
+    let dimacs = dimacs_of(&["....", "....", "*..*"], BoardVec::new(1, 0));
+    let variable = |pos: BoardVec| dimacs.variables.iter().position(|&v| v == pos).unwrap() as i32 + 1;
+    assert!(dimacs
+      .cnf
+      .contains(&format!("c {} = (0, 2)", variable(BoardVec::new(0, 2)))));
+
+    assert!(satisfiable(&dimacs.cnf, &[]));
+    assert!(satisfiable(&dimacs.cnf, &[variable(BoardVec::new(0, 2))]));
+    assert!(!satisfiable(&dimacs.cnf, &[-variable(BoardVec::new(0, 2))]));
+    assert!(!satisfiable(&dimacs.cnf, &[variable(BoardVec::new(1, 2))]));
+  }
+
+  #[test]
+  fn open_cells_allow_both_values() {
+    let dimacs = dimacs_of(&["..", "..", "*."], BoardVec::new(0, 0));
+    assert_eq!(dimacs.variables, vec![BoardVec::new(0, 2), BoardVec::new(1, 2)]);
+    assert!(satisfiable(&dimacs.cnf, &[1]));
+    assert!(satisfiable(&dimacs.cnf, &[-1]));
+    // The global count allows only one mine.
+    assert!(!satisfiable(&dimacs.cnf, &[1, 2]));
+  }
+}
@@ -0,0 +1,313 @@
+use super::probability::{Constraint, Region};
+use super::FieldKnowledge::*;
+use super::State;
+use crate::board::{neighbourhood_size, BoardVec};
+
+/// Picks the cell to open when nothing more can be deduced.
+pub trait GuessHeuristic {
+  fn guess(&mut self, state: &State) -> Option<BoardVec>;
+}
+
+impl<F: FnMut(&State) -> Option<BoardVec>> GuessHeuristic for F {
+  fn guess(&mut self, state: &State) -> Option<BoardVec> {
+    self(state)
+  }
+}
+
+/// Opens the hidden cell that is least likely to be a mine.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LowestProbability;
+
+impl GuessHeuristic for LowestProbability {
+  fn guess(&mut self, state: &State) -> Option<BoardVec> {
+    let probabilities = state.mine_probabilities();
+    state
+      .board
+      .positions()
+      .filter(|&pos| matches!(state.board[pos], Unknown | NoMine))
+      .min_by(|&a, &b| probabilities[a].total_cmp(&probabilities[b]))
+  }
+}
+
+/// Opens a provably safe cell if there is one. Otherwise opens the frontier cell whose number is expected
+/// to rule out the largest share of its region's solutions, weighted by the chance of surviving the click.
+/// The global mine count is ignored. Falls back to `LowestProbability` if no click would tell anything new.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EntropyGuess;
+
+impl GuessHeuristic for EntropyGuess {
+  fn guess(&mut self, state: &State) -> Option<BoardVec> {
+    let lowest = LowestProbability.guess(state)?;
+    if state.mine_probabilities()[lowest] == 0.0 {
+      return Some(lowest);
+    }
+
+    let mut best: Option<(BoardVec, f64)> = None;
+    for region in state.frontier_regions() {
+      let neighbours: Vec<Vec<usize>> = region
+        .cells
+        .iter()
+        .map(|&cell| {
+          (0..region.cells.len())
+            .filter(|&i| {
+              let d = region.cells[i] - cell;
+              d != BoardVec::new(0, 0) && d.x.abs() <= state.radius && d.y.abs() <= state.radius
+            })
+            .collect()
+        })
+        .collect();
+
+      let mut total = 0.0;
+      let mut outcomes = vec![vec![0.0; neighbourhood_size(state.radius) as usize + 1]; region.cells.len()];
+      region.enumerate_solutions(|assignment| {
+        total += 1.0;
+        for (cell, cell_outcomes) in outcomes.iter_mut().enumerate() {
+          if !assignment[cell] {
+            let number = neighbours[cell].iter().filter(|&&i| assignment[i]).count();
+            cell_outcomes[number] += 1.0;
+          }
+        }
+      });
+
+      for (&cell, cell_outcomes) in region.cells.iter().zip(&outcomes) {
+        let safe: f64 = cell_outcomes.iter().sum();
+        if safe == 0.0 {
+          continue;
+        }
+        let collision: f64 = cell_outcomes.iter().map(|n| (n / safe) * (n / safe)).sum();
+        let score = safe / total * (1.0 - collision);
+        if score > best.map_or(0.0, |(_, best_score)| best_score) {
+          best = Some((cell, score));
+        }
+      }
+    }
+
+    Some(best.map_or(lowest, |(cell, _)| cell))
+  }
+}
+
+/// Endgames with more hidden cells than this are too expensive for `State::minimax_guess`.
+pub const MINIMAX_MAX_CELLS: usize = 24;
+
+/// Opens the cell chosen by `State::minimax_guess` with the given search depth.
+#[derive(Debug, Clone, Copy)]
+pub struct Minimax {
+  pub depth: u32,
+}
+
+impl GuessHeuristic for Minimax {
+  fn guess(&mut self, state: &State) -> Option<BoardVec> {
+    state.minimax_guess(self.depth)
+  }
+}
+
+impl State {
+  /// Picks the guess that minimizes the number of further guesses needed in the worst case, looking `depth`
+  /// guesses ahead. Ties go to the cell least likely to be a mine. Returns a safe cell if there is one, also
+  /// one that is only safe because of the mine count, and falls back to `LowestProbability` if more than
+  /// `MINIMAX_MAX_CELLS` cells are hidden.
+  pub fn minimax_guess(&self, depth: u32) -> Option<BoardVec> {
+    if let Some(safe) = self.suggestions().next() {
+      return Some(safe);
+    }
+    let cells: Vec<BoardVec> = self
+      .board
+      .positions()
+      .filter(|&pos| self.board[pos] == Unknown)
+      .collect();
+    if cells.len() > MINIMAX_MAX_CELLS {
+      return LowestProbability.guess(self);
+    }
+
+    let endgame = Endgame::new(self, &cells);
+    let all: Vec<&[bool]> = endgame.solutions.iter().map(Vec::as_slice).collect();
+    // Cells can be safe because of the mine count alone, which propagation does not see.
+    if let Some(safe) = (0..cells.len()).find(|&cell| !all.is_empty() && all.iter().all(|s| !s[cell])) {
+      return Some(cells[safe]);
+    }
+    let mut opened = vec![false; cells.len()];
+    let candidates: Vec<usize> = (0..cells.len())
+      .filter(|&cell| endgame.is_uncertain(&all, &opened, cell))
+      .collect();
+    candidates
+      .into_iter()
+      .min_by_key(|&cell| {
+        let mines = all.iter().filter(|solution| solution[cell]).count();
+        (endgame.guess(&all, &mut opened, cell, depth), mines)
+      })
+      .map(|cell| cells[cell])
+  }
+}
+
+/// All mine assignments of the hidden cells that agree with the explored cells and the mine count.
+/// Each of them is equally likely.
+struct Endgame {
+  solutions: Vec<Vec<bool>>,
+  neighbours: Vec<Vec<usize>>,
+}
+
+impl Endgame {
+  fn new(state: &State, cells: &[BoardVec]) -> Self {
+    let index = |pos: BoardVec| cells.iter().position(|&cell| cell == pos);
+    let mut constraints: Vec<Constraint> = state
+      .board
+      .enumerate()
+      .filter_map(|(pos, knowledge)| match knowledge {
+        Explored(explored) if explored.unknowns > 0 => Some(Constraint {
+          pos,
+          mines: explored.mines_left,
+          cells: pos.neighbours_in_radius(state.radius).filter_map(index).collect(),
+        }),
+        _ => None,
+      })
+      .collect();
+    // The global mine count, attributed to the board's origin.
+    constraints.push(Constraint {
+      pos: state.board.origin,
+      mines: state.mines_left,
+      cells: (0..cells.len()).collect(),
+    });
+
+    let region = Region {
+      cells: cells.to_vec(),
+      constraints,
+    };
+    let mut solutions = Vec::new();
+    region.enumerate_solutions(|assignment| solutions.push(assignment.to_vec()));
+    let neighbours = cells
+      .iter()
+      .map(|&cell| cell.neighbours_in_radius(state.radius).filter_map(index).collect())
+      .collect();
+    Self { solutions, neighbours }
+  }
+
+  fn is_uncertain(&self, solutions: &[&[bool]], opened: &[bool], cell: usize) -> bool {
+    !opened[cell] && solutions.iter().any(|s| s[cell]) && solutions.iter().any(|s| !s[cell])
+  }
+
+  /// Splits the solutions in which `cell` is safe by the number it would show.
+  fn outcomes<'s>(&self, solutions: &[&'s [bool]], cell: usize) -> Vec<Vec<&'s [bool]>> {
+    let mut outcomes = vec![Vec::new(); self.neighbours[cell].len() + 1];
+    for &solution in solutions.iter().filter(|s| !s[cell]) {
+      let number = self.neighbours[cell].iter().filter(|&&n| solution[n]).count();
+      outcomes[number].push(solution);
+    }
+    outcomes.retain(|outcome| !outcome.is_empty());
+    outcomes
+  }
+
+  /// Worst-case number of guesses after surviving a guess at `cell`, counting this one.
+  fn guess(&self, solutions: &[&[bool]], opened: &mut [bool], cell: usize, depth: u32) -> u32 {
+    opened[cell] = true;
+    let worst = self
+      .outcomes(solutions, cell)
+      .iter()
+      .map(|outcome| self.remaining(outcome, opened, depth.saturating_sub(1)))
+      .max()
+      .unwrap_or(0);
+    opened[cell] = false;
+    1 + worst
+  }
+
+  /// Worst-case number of guesses needed to tell the solutions apart. Safe cells are opened for free.
+  fn remaining(&self, solutions: &[&[bool]], opened: &mut [bool], depth: u32) -> u32 {
+    if solutions.len() <= 1 {
+      return 0;
+    }
+    if let Some(safe) = (0..opened.len()).find(|&cell| !opened[cell] && solutions.iter().all(|s| !s[cell])) {
+      opened[safe] = true;
+      let worst = self
+        .outcomes(solutions, safe)
+        .iter()
+        .map(|outcome| self.remaining(outcome, opened, depth))
+        .max()
+        .unwrap_or(0);
+      opened[safe] = false;
+      return worst;
+    }
+    if depth == 0 {
+      return 1;
+    }
+    let candidates: Vec<usize> = (0..opened.len())
+      .filter(|&cell| self.is_uncertain(solutions, opened, cell))
+      .collect();
+    candidates
+      .into_iter()
+      .map(|cell| self.guess(solutions, opened, cell, depth))
+      .min()
+      .unwrap_or(0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::board::Board;
+  use crate::Field;
+
+  /// The view of rows of digits (`.` for hidden cells) with `mines` mines in total.
+  fn state(rows: &[&str], mines: u32) -> State {
+    let mut view = Board::new(rows[0].len() as u32, rows.len() as u32, None);
+    for (y, row) in rows.iter().enumerate() {
+      for (x, c) in row.chars().enumerate() {
+        view[BoardVec::new(x as i32, y as i32)] = c.to_digit(10).map(Field::Empty);
+      }
+    }
+    State::from_view(&view, mines)
+  }
+
+  #[test]
+  fn entropy_takes_a_little_more_risk_for_information() {
+    let state = state(
+      &[
+        "........", "..222...", "..101...", "..112...", "........", "........", "........", "........",
+      ],
+      12,
+    );
+    let probabilities = state.mine_probabilities();
+    let lowest = LowestProbability.guess(&state).unwrap();
+    let informative = EntropyGuess.guess(&state).unwrap();
+    assert_eq!(lowest, BoardVec::new(2, 4));
+    assert_eq!(informative, BoardVec::new(1, 0));
+    assert!(probabilities[informative] > probabilities[lowest]);
+  }
+
+  #[test]
+  fn entropy_opens_safe_cells_first() {
+    // The only mine is next to both numbers, so it is (1, 0) or (1, 1) and everything else is safe.
+    let state = state(&["1..", "1..", "..."], 1);
+    let safe = EntropyGuess.guess(&state).unwrap();
+    assert_eq!(state.mine_probabilities()[safe], 0.0);
+  }
+
+  #[test]
+  fn minimax_avoids_the_greedy_guess() {
+    let state = state(&["00000", "12221", ".....", "....."], 4);
+    let cells: Vec<BoardVec> = state
+      .board
+      .positions()
+      .filter(|&pos| state.board[pos] == Unknown)
+      .collect();
+    let endgame = Endgame::new(&state, &cells);
+    let all: Vec<&[bool]> = endgame.solutions.iter().map(Vec::as_slice).collect();
+    let worst_case = |pos: BoardVec| {
+      let cell = cells.iter().position(|&cell| cell == pos).unwrap();
+      endgame.guess(&all, &mut vec![false; cells.len()], cell, 2)
+    };
+
+    let greedy = LowestProbability.guess(&state).unwrap();
+    let minimax = state.minimax_guess(2).unwrap();
+    assert_eq!(greedy, BoardVec::new(0, 3));
+    assert_eq!(minimax, BoardVec::new(0, 2));
+    assert!(worst_case(minimax) < worst_case(greedy));
+  }
+
+  #[test]
+  fn minimax_opens_cells_that_propagation_misses() {
+    // Neither number decides anything alone, but together only (1, 0) and (1, 1) can hold the mine.
+    let state = state(&["1..", "1..", "..."], 1);
+    assert_eq!(state.suggestions().count(), 0);
+    let safe = state.minimax_guess(2).unwrap();
+    assert_eq!(state.mine_probabilities()[safe], 0.0);
+  }
+}
@@ -0,0 +1,680 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use super::FieldKnowledge::*;
+use super::{FieldKnowledge, State};
+use crate::board::{Board, BoardExplorer, BoardVec};
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Constraint {
+  pub pos: BoardVec,
+  pub mines: u32,
+  pub cells: Vec<usize>,
+}
+
+/// A connected set of unknown frontier cells together with the explored cells constraining them.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Region {
+  pub cells: Vec<BoardVec>,
+  pub constraints: Vec<Constraint>,
+}
+
+impl Region {
+  /// Calls `visit` with every mine assignment of `cells` that satisfies all constraints.
+  pub fn enumerate_solutions(&self, visit: impl FnMut(&[bool])) {
+    let mut cell_constraints = vec![Vec::new(); self.cells.len()];
+    for (i, constraint) in self.constraints.iter().enumerate() {
+      for &cell in &constraint.cells {
+        cell_constraints[cell].push(i);
+      }
+    }
+
+    let mut enumerator = Enumerator {
+      region: self,
+      cell_constraints,
+      placed: vec![0; self.constraints.len()],
+      open: self.constraints.iter().map(|c| c.cells.len() as u32).collect(),
+      assignment: vec![false; self.cells.len()],
+      visit,
+    };
+    enumerator.run(0);
+  }
+}
+
+struct Enumerator<'a, F> {
+  region: &'a Region,
+  cell_constraints: Vec<Vec<usize>>,
+  placed: Vec<u32>,
+  open: Vec<u32>,
+  assignment: Vec<bool>,
+  visit: F,
+}
+
+impl<'a, F: FnMut(&[bool])> Enumerator<'a, F> {
+  fn run(&mut self, index: usize) {
+    if index == self.assignment.len() {
+      (self.visit)(&self.assignment);
+      return;
+    }
+
+    for mine in [false, true] {
+      if self.fits(index, mine) {
+        self.assignment[index] = mine;
+        for &c in &self.cell_constraints[index] {
+          self.placed[c] += mine as u32;
+          self.open[c] -= 1;
+        }
+
+        self.run(index + 1);
+
+        for &c in &self.cell_constraints[index] {
+          self.placed[c] -= mine as u32;
+          self.open[c] += 1;
+        }
+      }
+    }
+    self.assignment[index] = false;
+  }
+
+  fn fits(&self, index: usize, mine: bool) -> bool {
+    self.cell_constraints[index].iter().all(|&c| {
+      let mines = self.region.constraints[c].mines;
+      let placed = self.placed[c] + mine as u32;
+      let open = self.open[c] - 1;
+      placed <= mines && placed + open >= mines
+    })
+  }
+}
+
+/// Number of solutions of a region per mine count, and how often each cell is a mine in them.
+struct RegionSolutions {
+  counts: Vec<f64>,
+  cell_counts: Vec<Vec<f64>>,
+}
+
+impl RegionSolutions {
+  fn new(region: &Region) -> Self {
+    if let [constraint] = region.constraints.as_slice() {
+      debug_assert_eq!(constraint.cells.len(), region.cells.len());
+      return Self::single_constraint(region.cells.len(), constraint.mines as usize);
+    }
+
+    let size = region.cells.len();
+    let mut counts = vec![0.0; size + 1];
+    let mut cell_counts = vec![vec![0.0; size]; size + 1];
+    region.enumerate_solutions(|assignment| {
+      let mines = assignment.iter().filter(|&&mine| mine).count();
+      counts[mines] += 1.0;
+      for (i, _) in assignment.iter().enumerate().filter(|(_, &mine)| mine) {
+        cell_counts[mines][i] += 1.0;
+      }
+    });
+
+    Self { counts, cell_counts }
+  }
+
+  /// Closed form for a region bordering only one explored cell: every choice of `mines` cells is a solution.
+  fn single_constraint(size: usize, mines: usize) -> Self {
+    let mut counts = vec![0.0; size + 1];
+    let mut cell_counts = vec![vec![0.0; size]; size + 1];
+    if mines <= size {
+      counts[mines] = binomial(size, mines);
+      if mines > 0 {
+        cell_counts[mines] = vec![binomial(size - 1, mines - 1); size];
+      }
+    }
+
+    Self { counts, cell_counts }
+  }
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+  (0..k).fold(1.0, |acc, i| acc * (n - i) as f64 / (i + 1) as f64)
+}
+
+impl State {
+  fn is_frontier(&self, pos: BoardVec) -> bool {
+    self.board[pos] == Unknown && self.around(pos).any(|k| matches!(k, Explored(_)))
+  }
+
+  fn around(&self, pos: BoardVec) -> impl Iterator<Item = &FieldKnowledge> {
+    self.board.around_in_radius(pos, self.radius).map(|(_, k)| k)
+  }
+
+  /// Unknown cells next to at least one explored cell, in row-major order.
+  pub fn frontier(&self) -> impl Iterator<Item = BoardVec> + '_ {
+    self.board.positions().filter(move |&pos| self.is_frontier(pos))
+  }
+
+  /// Frontier cells that may or may not be mines, i.e. the cells a player would have to guess among.
+  /// Cells whose state follows from combining constraints are left out as well.
+  pub fn uncertain_cells(&self) -> Vec<BoardVec> {
+    let probabilities = self.mine_probabilities();
+    self
+      .frontier()
+      .filter(|&pos| probabilities[pos] > 0.0 && probabilities[pos] < 1.0)
+      .collect()
+  }
+
+  pub fn frontier_regions(&self) -> Vec<Region> {
+    let board = &self.board;
+    let mut explorer = BoardExplorer::from(board);
+    let mut seen_constraints = board.map(|_| false);
+    let mut regions = Vec::new();
+
+    for pos in board.positions() {
+      if !self.is_frontier(pos) || !explorer.enqueue(pos) {
+        continue;
+      }
+
+      let mut cells = Vec::new();
+      let mut constraint_positions = Vec::new();
+      while let Some(cell) = explorer.pop() {
+        cells.push(cell);
+        for (neighbour_pos, knowledge) in board.around_in_radius(cell, self.radius) {
+          if let Explored(_) = knowledge {
+            if !seen_constraints[neighbour_pos] {
+              seen_constraints[neighbour_pos] = true;
+              constraint_positions.push(neighbour_pos);
+              explorer.enqueue_all(
+                board
+                  .around_in_radius(neighbour_pos, self.radius)
+                  .filter(|&(_, &k)| k == Unknown)
+                  .map(|(n, _)| n),
+              );
+            }
+          }
+        }
+      }
+
+      let indices: HashMap<BoardVec, usize> = cells.iter().enumerate().map(|(i, &cell)| (cell, i)).collect();
+      // A constraint over the same cells and mines as an earlier one adds nothing, so it is left out of the
+      // enumeration. This is what a superset with the same number of mines turns into once propagation has
+      // resolved the extra cells.
+      let mut seen = HashSet::new();
+      let constraints = constraint_positions
+        .into_iter()
+        .map(|pos| {
+          let mut cells: Vec<usize> = pos
+            .neighbours_in_radius(self.radius)
+            .filter_map(|n| indices.get(&n).copied())
+            .collect();
+          cells.sort_unstable();
+          Constraint {
+            pos,
+            mines: match board[pos] {
+              Explored(explored) => explored.mines_left,
+              _ => unreachable!(),
+            },
+            cells,
+          }
+        })
+        .filter(|constraint| seen.insert((constraint.cells.clone(), constraint.mines)))
+        .collect();
+
+      regions.push(Region { cells, constraints });
+    }
+
+    regions
+  }
+
+  /// Exact probability of each cell to contain a mine, taking the global mine count into account.
+  pub fn mine_probabilities(&self) -> Board<f64> {
+    let regions = self.frontier_regions();
+    let solutions: Vec<RegionSolutions> = regions.iter().map(RegionSolutions::new).collect();
+    self.probabilities_from(&regions, &solutions.iter().collect::<Vec<_>>())
+  }
+
+  fn probabilities_from(&self, regions: &[Region], solutions: &[&RegionSolutions]) -> Board<f64> {
+    let frontier_size: usize = regions.iter().map(|region| region.cells.len()).sum();
+    let unknowns = self.board.iter().filter(|&&k| k == Unknown).count();
+    let interior = unknowns - frontier_size;
+    let mines = self.mines_left as usize;
+    let interior_weights = interior_weights(interior, mines, frontier_size);
+
+    let mut prefix = vec![vec![1.0]];
+    for region in solutions {
+      prefix.push(convolve(prefix.last().unwrap(), &region.counts));
+    }
+    let mut suffix = vec![vec![1.0]; solutions.len() + 1];
+    for (i, region) in solutions.iter().enumerate().rev() {
+      suffix[i] = convolve(&region.counts, &suffix[i + 1]);
+    }
+
+    let mut result = self.board.map(|knowledge| if *knowledge == Mine { 1.0 } else { 0.0 });
+
+    for (i, (region, region_solutions)) in regions.iter().zip(solutions).enumerate() {
+      let others = convolve(&prefix[i], &suffix[i + 1]);
+      let weight = |k: usize| -> f64 {
+        others
+          .iter()
+          .enumerate()
+          .map(|(s, w)| w * interior_weights.get(s + k).copied().unwrap_or(0.0))
+          .sum()
+      };
+
+      let mut total = 0.0;
+      let mut cell_totals = vec![0.0; region.cells.len()];
+      for (k, &count) in region_solutions.counts.iter().enumerate() {
+        if count == 0.0 {
+          continue;
+        }
+        let w = weight(k);
+        total += count * w;
+        for (cell_total, cell_count) in cell_totals.iter_mut().zip(&region_solutions.cell_counts[k]) {
+          *cell_total += cell_count * w;
+        }
+      }
+
+      if total > 0.0 {
+        for (&pos, cell_total) in region.cells.iter().zip(cell_totals) {
+          result[pos] = cell_total / total;
+        }
+      }
+    }
+
+    if interior > 0 {
+      let all = prefix.last().unwrap();
+      let (mut expected, mut total) = (0.0, 0.0);
+      for (s, w) in all.iter().enumerate() {
+        let w = w * interior_weights.get(s).copied().unwrap_or(0.0);
+        total += w;
+        expected += w * mines.saturating_sub(s) as f64;
+      }
+
+      if total > 0.0 {
+        let probability = expected / total / interior as f64;
+        for (pos, knowledge) in self.board.enumerate() {
+          if *knowledge == Unknown && !self.is_frontier(pos) {
+            result[pos] = probability;
+          }
+        }
+      }
+    }
+
+    result
+  }
+
+  /// Minimum and maximum number of mines consistent with the explored cells, ignoring the declared total.
+  /// An inconsistent view results in a minimum larger than the maximum.
+  pub fn mine_count_bounds(&self) -> (u32, u32) {
+    let known_mines: u32 = self.mine_counts.iter().sum();
+    let unknowns = self.board.iter().filter(|&&k| k == Unknown).count() as u32;
+
+    let (mut min, mut max) = (known_mines, known_mines + unknowns);
+    for region in self.frontier_regions() {
+      let counts = RegionSolutions::new(&region).counts;
+      let size = region.cells.len() as u32;
+      let region_min = counts.iter().position(|&c| c > 0.0).map_or(size + 1, |k| k as u32);
+      let region_max = counts.iter().rposition(|&c| c > 0.0).map_or(0, |k| k as u32);
+      min += region_min;
+      max = max + region_max - size;
+    }
+
+    (min, max)
+  }
+
+  /// The provably safe cell that opens the most hidden cells, see `opened_region`.
+  pub fn best_safe_opening(&self) -> Option<BoardVec> {
+    let probabilities = self.mine_probabilities();
+
+    self
+      .board
+      .positions()
+      .filter(|&pos| self.is_hidden(pos) && probabilities[pos] == 0.0)
+      .min_by_key(|&pos| Reverse(self.opened_region(pos, &probabilities)))
+  }
+
+  /// Number of hidden cells opened by the safe cell `start`. A cell whose neighbours are all known
+  /// to be safe is blank, so the region follows the zero cascade through such cells.
+  fn opened_region(&self, start: BoardVec, probabilities: &Board<f64>) -> usize {
+    let mut region = HashSet::from([start]);
+    let mut pending = vec![start];
+    while let Some(pos) = pending.pop() {
+      let neighbours: Vec<BoardVec> = self.board.neighbours_in_bounds(pos, self.radius).collect();
+      if neighbours.iter().all(|&n| probabilities[n] == 0.0) {
+        for n in neighbours {
+          if self.is_hidden(n) && region.insert(n) {
+            pending.push(n);
+          }
+        }
+      }
+    }
+    region.len()
+  }
+
+  fn is_hidden(&self, pos: BoardVec) -> bool {
+    matches!(self.board[pos], Unknown | NoMine)
+  }
+
+  /// True if every unknown frontier cell is provably a mine or provably safe.
+  pub fn is_fully_determined(&self) -> bool {
+    let probabilities = self.mine_probabilities();
+    self
+      .frontier()
+      .all(|pos| probabilities[pos] == 0.0 || probabilities[pos] == 1.0)
+  }
+}
+
+/// Computes mine probabilities like `State::mine_probabilities`, but remembers the solutions of each
+/// frontier region so that regions which did not change since the last call are not enumerated again.
+#[derive(Default)]
+pub struct ProbabilitySolver {
+  cache: HashMap<Region, RegionSolutions>,
+  enumerations: usize,
+}
+
+impl ProbabilitySolver {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Number of regions enumerated so far.
+  pub fn enumerations(&self) -> usize {
+    self.enumerations
+  }
+
+  pub fn mine_probabilities(&mut self, state: &State) -> Board<f64> {
+    let regions = state.frontier_regions();
+    let mut cache = HashMap::with_capacity(regions.len());
+    for region in &regions {
+      let solutions = self.cache.remove(region).unwrap_or_else(|| {
+        self.enumerations += 1;
+        RegionSolutions::new(region)
+      });
+      cache.insert(region.clone(), solutions);
+    }
+    self.cache = cache;
+
+    let solutions: Vec<&RegionSolutions> = regions.iter().map(|region| &self.cache[region]).collect();
+    state.probabilities_from(&regions, &solutions)
+  }
+}
+
+/// Relative number of ways to place the remaining mines into the interior,
+/// indexed by the number of mines placed in the frontier.
+fn interior_weights(interior: usize, mines: usize, frontier_size: usize) -> Vec<f64> {
+  let mut ln_binomial = vec![0.0; interior.min(mines) + 1];
+  for t in 1..ln_binomial.len() {
+    ln_binomial[t] = ln_binomial[t - 1] + ((interior - t + 1) as f64).ln() - (t as f64).ln();
+  }
+
+  let ln_weights: Vec<f64> = (0..=frontier_size)
+    .map(|s| match mines.checked_sub(s) {
+      Some(t) if t <= interior => ln_binomial[t],
+      _ => f64::NEG_INFINITY,
+    })
+    .collect();
+  let max = ln_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+  ln_weights
+    .into_iter()
+    .map(|w| if max.is_finite() { (w - max).exp() } else { 0.0 })
+    .collect()
+}
+
+/// Convolution of two mine count distributions, scaled so that its largest entry is 1.
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+  let mut result = vec![0.0; a.len() + b.len() - 1];
+  for (i, x) in a.iter().enumerate() {
+    for (j, y) in b.iter().enumerate() {
+      result[i + j] += x * y;
+    }
+  }
+
+  let max = result.iter().copied().fold(0.0, f64::max);
+  if max > 0.0 {
+    result.iter_mut().for_each(|w| *w /= max);
+  }
+  result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+  use rand::rngs::StdRng;
+  use rand::SeedableRng;
+
+  use super::ProbabilitySolver;
+  use crate::board::{Board, BoardVec};
+  use crate::solver::State;
+  use crate::{Field, FirstClickPolicy, Game, GameSetup, GameSetupBuilder};
+
+  /// Mine probability of every hidden cell, found by trying all placements of the game's mines.
+  pub(crate) fn brute_force_probabilities(game: &Game) -> Board<f64> {
+    let hidden: Vec<BoardVec> = game.board().positions().filter(|&pos| !game.is_visible(pos)).collect();
+    assert!(hidden.len() <= 20, "Too many hidden cells for brute force");
+
+    let mut counts = game.board().map(|_| 0u64);
+    let mut total = 0u64;
+    for mask in 0u32..1 << hidden.len() {
+      if mask.count_ones() != game.setup().mines {
+        continue;
+      }
+      let mut placed = game.board().map(|_| false);
+      for (i, &pos) in hidden.iter().enumerate() {
+        placed[pos] = mask >> i & 1 == 1;
+      }
+      let consistent = game.board().positions().all(|pos| match game.view(pos) {
+        Some(Field::Empty(mines)) => {
+          let around = game.board().neighbours_in_bounds(pos, game.setup().radius());
+          around.filter(|&n| placed[n]).count() as u32 == mines
+        }
+        _ => true,
+      });
+      if consistent {
+        total += 1;
+        for &pos in hidden.iter().filter(|&&pos| placed[pos]) {
+          counts[pos] += 1;
+        }
+      }
+    }
+    counts.map(|&count| count as f64 / total as f64)
+  }
+
+  /// The game of `rows` (see `GameSetup::from_reader`) opened at `start`.
+  fn opened(rows: &[&str], start: BoardVec) -> Game {
+    let mut game = Game::from(GameSetup::from_reader(rows.join("\n").as_bytes()).unwrap());
+    game.open(start);
+    game
+  }
+
+  pub(crate) fn random_game(width: u32, height: u32, mines: u32, seed: u64) -> Game {
+    let start = BoardVec::new(0, 0);
+    let mut builder = GameSetupBuilder::with_rng(width, height, Box::new(StdRng::seed_from_u64(seed)));
+    builder.protect_start(start, FirstClickPolicy::ThreeByThree);
+    assert!(builder.add_random_mines(mines));
+    let mut game = Game::from(builder);
+    game.open(start);
+    game
+  }
+
+  #[test]
+  fn probabilities_match_brute_force() {
+    let mut checked = 0;
+    for seed in 0..200 {
+      let game = random_game(5, 4, 5, seed);
+      if game.board().positions().filter(|&pos| !game.is_visible(pos)).count() > 16 {
+        continue;
+      }
+      checked += 1;
+
+      let probabilities = State::from(&game).mine_probabilities();
+      let expected = brute_force_probabilities(&game);
+      for pos in game.board().positions().filter(|&pos| !game.is_visible(pos)) {
+        assert!(
+          (probabilities[pos] - expected[pos]).abs() < 1e-9,
+          "Seed {} at {:?}: {} instead of {}\n{:?}",
+          seed,
+          pos,
+          probabilities[pos],
+          expected[pos],
+          game
+        );
+      }
+    }
+    assert!(checked > 20);
+  }
+
+  #[test]
+  fn probabilities_on_origin_board() {
+    let mut view = Board::new(3, 2, None).with_origin(BoardVec::new(5, 5));
+    view[BoardVec::new(5, 5)] = Some(Field::Empty(1));
+    let state = State::from_view(&view, 1);
+    let probabilities = state.mine_probabilities();
+    assert_eq!(probabilities[BoardVec::new(6, 5)], 1.0 / 3.0);
+    assert_eq!(probabilities[BoardVec::new(7, 6)], 0.0);
+    assert_eq!(state.frontier_regions().len(), 1);
+  }
+
+  #[test]
+  fn best_safe_opening_follows_the_cascade() {
+    // Two hidden safe stretches: three cells, then five.
+    let mut view = Board::new(9, 1, None);
+    view[BoardVec::new(3, 0)] = Some(Field::Empty(0));
+    let state = State::from_view(&view, 0);
+    let probabilities = state.mine_probabilities();
+    assert_eq!(state.opened_region(BoardVec::new(1, 0), &probabilities), 3);
+    assert_eq!(state.opened_region(BoardVec::new(8, 0), &probabilities), 5);
+    assert_eq!(state.best_safe_opening(), Some(BoardVec::new(4, 0)));
+  }
+
+  #[test]
+  fn opened_region_stops_at_numbers() {
+    let mut view = Board::new(4, 1, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    let state = State::from_view(&view, 1);
+    let probabilities = state.mine_probabilities();
+    assert_eq!(probabilities[BoardVec::new(1, 0)], 1.0);
+    assert_eq!(state.opened_region(BoardVec::new(2, 0), &probabilities), 1);
+    assert_eq!(state.opened_region(BoardVec::new(3, 0), &probabilities), 2);
+    assert_eq!(state.best_safe_opening(), Some(BoardVec::new(3, 0)));
+  }
+
+  #[test]
+  fn fully_determined_only_without_guesses() {
+    let must_guess = opened(&["..", "..", "*."], BoardVec::new(0, 0));
+    assert!(!State::from(&must_guess).is_fully_determined());
+
+    let deducible = opened(&["..*.."], BoardVec::new(0, 0));
+    assert!(!deducible.is_win());
+    let state = State::from(&deducible);
+    assert!(state.is_fully_determined());
+    assert!(state.suggestions().next().is_some());
+
+    // A 1-2-1 above three hidden cells only fits mine, safe, mine, but no single number decides it.
+    let mut view = Board::new(3, 2, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    view[BoardVec::new(1, 0)] = Some(Field::Empty(2));
+    view[BoardVec::new(2, 0)] = Some(Field::Empty(1));
+    let state = State::from_view(&view, 2);
+    assert_eq!(state.frontier().count(), 3);
+    assert!(state.is_fully_determined());
+  }
+
+  #[test]
+  fn mine_count_bounds_of_the_view() {
+    let game = opened(&["....", "....", "**..", "*..."], BoardVec::new(3, 0));
+    assert_eq!(State::from(&game).mine_count_bounds(), (2, 3));
+
+    // The declared total of 1 lies within the bounds, 3 does not.
+    let mut view = Board::new(3, 1, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    let (min, max) = State::from_view(&view, 1).mine_count_bounds();
+    assert_eq!((min, max), (1, 2));
+    assert!(!(min..=max).contains(&3));
+  }
+
+  #[test]
+  fn probability_solver_only_enumerates_changed_regions() {
+    let rows = ["..*.......*..", ".............", ".............", "*...........*"];
+    let mut game = opened(&rows, BoardVec::new(1, 0));
+    game.open(BoardVec::new(11, 0));
+    let mut solver = ProbabilitySolver::new();
+    assert!(solver.mine_probabilities(&State::from(&game)) == State::from(&game).mine_probabilities());
+    assert_eq!(solver.enumerations(), 2);
+
+    game.open(BoardVec::new(12, 0));
+    assert!(solver.mine_probabilities(&State::from(&game)) == State::from(&game).mine_probabilities());
+    assert_eq!(solver.enumerations(), 3);
+
+    solver.mine_probabilities(&State::from(&game));
+    assert_eq!(solver.enumerations(), 3);
+  }
+
+  #[test]
+  fn frontier_is_the_ring_around_numbers() {
+    let mut view = Board::new(5, 5, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    view[BoardVec::new(2, 2)] = Some(Field::Empty(1));
+    let state = State::from_view(&view, 2);
+    let ring = [
+      (1, 0),
+      (0, 1),
+      (1, 1),
+      (2, 1),
+      (3, 1),
+      (1, 2),
+      (3, 2),
+      (1, 3),
+      (2, 3),
+      (3, 3),
+    ];
+    let expected: Vec<BoardVec> = ring.iter().map(|&(x, y)| BoardVec::new(x, y)).collect();
+    assert_eq!(state.frontier().collect::<Vec<_>>(), expected);
+  }
+
+  #[test]
+  fn single_constraint_regions_match_brute_force() {
+    let mut game = Game::from(GameSetup::from_reader("*...*\n.....\n..*..\n*....".as_bytes()).unwrap());
+    // (0, 2) borders a region of its own, (4, 1) and (3, 3) share some unknowns.
+    for (x, y) in [(0, 2), (4, 1), (3, 3)] {
+      game.force_open(BoardVec::new(x, y), false);
+    }
+    let probabilities = State::from(&game).mine_probabilities();
+    let expected = brute_force_probabilities(&game);
+    for pos in game.board().positions().filter(|&pos| !game.is_visible(pos)) {
+      assert!((probabilities[pos] - expected[pos]).abs() < 1e-9, "{:?}", pos);
+    }
+  }
+
+  #[test]
+  fn uncertain_cells_leave_out_forced_cells() {
+    // The 1-1 pattern makes (2, 0) and (2, 1) safe, the mine is in (0, 1) or (1, 1).
+    let mut game = Game::from(GameSetup::from_reader("....\n*...\n....".as_bytes()).unwrap());
+    game.force_open(BoardVec::new(0, 0), false);
+    game.force_open(BoardVec::new(1, 0), false);
+    let state = State::from(&game);
+    assert_eq!(state.frontier().count(), 4);
+    assert_eq!(state.uncertain_cells(), vec![BoardVec::new(0, 1), BoardVec::new(1, 1)]);
+  }
+
+  #[test]
+  fn duplicate_constraints_are_left_out() {
+    // Both numbers see exactly (1, 0) and (1, 1).
+    let mut view = Board::new(2, 2, None);
+    view[BoardVec::new(0, 0)] = Some(Field::Empty(1));
+    view[BoardVec::new(0, 1)] = Some(Field::Empty(1));
+    let state = State::from_view(&view, 1);
+    let regions = state.frontier_regions();
+    assert_eq!(regions.len(), 1);
+    assert_eq!(regions[0].constraints.len(), 1);
+    let probabilities = state.mine_probabilities();
+    assert_eq!(probabilities[BoardVec::new(1, 0)], 0.5);
+    assert_eq!(probabilities[BoardVec::new(1, 1)], 0.5);
+  }
+
+  #[test]
+  fn dominated_constraints_keep_the_probabilities() {
+    let mut game = Game::from(GameSetup::from_reader("..*..\n.....\n..*..\n.....".as_bytes()).unwrap());
+    for (x, y) in [(0, 0), (0, 1), (0, 2), (1, 2), (0, 3), (1, 3), (4, 3), (2, 1)] {
+      game.force_open(BoardVec::new(x, y), false);
+    }
+    let state = State::from(&game);
+    let probabilities = state.mine_probabilities();
+    let cached = ProbabilitySolver::new().mine_probabilities(&state);
+    let expected = brute_force_probabilities(&game);
+    for pos in game.board().positions().filter(|&pos| !game.is_visible(pos)) {
+      assert!((probabilities[pos] - expected[pos]).abs() < 1e-9, "{:?}", pos);
+      assert!((cached[pos] - expected[pos]).abs() < 1e-9, "{:?}", pos);
+    }
+  }
+}